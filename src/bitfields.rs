@@ -1,4 +1,5 @@
 use crate::config::{Configuration, HeaterProfile, IIRFilter, Oversampling, SensorMode};
+use crate::error::ConfigError;
 use bitfield::bitfield;
 use core::time::Duration;
 
@@ -49,6 +50,14 @@ impl RawConfig<[u8; 5]> {
             self.set_heater_profile(HeaterProfile::Profile0);
         }
     }
+    /// Checks the registers for contradictory settings.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        // a heater profile is only used when the gas measurement is enabled
+        if self.heater_profile() != HeaterProfile::Profile0 && !self.run_gas() {
+            return Err(ConfigError::GasProfileWithoutGasEnabled);
+        }
+        Ok(())
+    }
 }
 
 bitfield! {
@@ -61,8 +70,10 @@ bitfield! {
 #[derive(Debug)]
 pub struct GasWaitDuration(Duration);
 impl From<u8> for GasWaitDuration {
+    // gas_wait<5:0> is the duration in ms, multiplied by 4^gas_wait<7:6>
     fn from(val: u8) -> Self {
-        Self(Duration::from_millis(val as u64))
+        let duration_ms = (val & 0x3F) as u64 * (1 << (2 * (val >> 6)));
+        Self(Duration::from_millis(duration_ms))
     }
 }
 
@@ -79,11 +90,21 @@ bitfield! {
     u8;
     pub from into Oversampling, temperature_os, set_temperature_os: 7, 5;
     pub from into Oversampling, pressure_os, set_pressure_os: 4, 2;
-    pub from into SensorMode, mode, set_mode: 1, 0;
+    pub raw_mode, set_raw_mode: 1, 0;
+}
+
+impl CtrlMeasurment {
+    /// Decoded mode bits, the raw bits if they don't encode sleep or forced mode
+    pub fn mode(&self) -> Result<SensorMode, u8> {
+        SensorMode::try_from(self.raw_mode())
+    }
+    pub fn set_mode(&mut self, mode: SensorMode) {
+        self.set_raw_mode(mode.into());
+    }
 }
 
 bitfield! {
-    pub struct MeasurmentStatus(u8);
+    pub struct RawMeasurementStatus(u8);
     impl Debug;
     u8;
     pub bool, new_data, _: 7;
@@ -174,13 +195,17 @@ fn calc_position(bit_position: usize, byte_offset: usize) -> usize {
     byte_offset * 8 + bit_position
 }
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)] // grouped by register fields
 mod tests {
     extern crate std;
-    use crate::config::Configuration;
+    use crate::config::{Configuration, HeaterProfile};
+    use crate::error::ConfigError;
     use std::println;
 
-    use super::{calc_position, Humidity, Measurment, RawConfig, RawData};
+    use super::{calc_position, GasWaitDuration, Humidity, Measurment, RawConfig, RawData};
+    use crate::config::GasConfig;
     use bitfield::bitfield;
+    use core::time::Duration;
 
     bitfield! {
         pub struct SampleData([u8]);
@@ -246,6 +271,29 @@ mod tests {
         assert!(raw_data.gas_range() == expected_gas_range);
     }
 
+    #[test]
+    fn test_gas_adc_assembly() {
+        // (gas_r_msb, gas_r_lsb, gas_adc) with gas_adc<9:2> in the msb and gas_adc<1:0> in bit 7/6 of the lsb
+        let vectors = [
+            (0x00, 0b00_0_0_0000, 0),
+            (0x00, 0b01_0_0_0000, 1),
+            (0x00, 0b10_0_0_0000, 2),
+            (0x00, 0b11_0_0_0000, 3),
+            (0x01, 0b00_0_0_0000, 4),
+            (0x80, 0b00_0_0_0000, 512),
+            (0xFF, 0b00_0_0_0000, 1020),
+            (0xFF, 0b11_0_0_0000, 1023),
+            // gas_valid, heater_stable and gas_range don't leak into the adc value
+            (0x00, 0b00_1_1_1111, 0),
+            (0xFF, 0b11_1_1_1111, 1023),
+        ];
+        for (gas_r_msb, gas_r_lsb, expected_gas_adc) in vectors {
+            let mut data = [0u8; 15];
+            data[13] = gas_r_msb;
+            data[14] = gas_r_lsb;
+            assert_eq!(RawData(data).gas_adc().0, expected_gas_adc);
+        }
+    }
     #[test]
     fn test_measurment_and_humidty() {
         // 2.5 bytes msb, lsb, xlsb 2 bytes msb, lsb
@@ -293,4 +341,25 @@ mod tests {
         println!("Actual raw data: {raw_data:?}");
         assert!(expected_raw_data == raw_data);
     }
+    #[test]
+    fn test_validate_raw_config() {
+        let mut raw_config = RawConfig([0u8; 5]);
+        raw_config.apply_config(&Configuration::default());
+        assert_eq!(raw_config.validate(), Ok(()));
+        raw_config.set_heater_profile(HeaterProfile::Profile3);
+        assert_eq!(raw_config.validate(), Ok(()));
+        raw_config.set_run_gas(false);
+        assert_eq!(
+            raw_config.validate(),
+            Err(ConfigError::GasProfileWithoutGasEnabled)
+        );
+    }
+    #[test]
+    fn test_gas_wait_round_trip() {
+        for (duration_ms, expected_ms) in [(0, 0), (63, 63), (100, 100), (150, 148), (4032, 4032)] {
+            let gas_config = GasConfig::new(Duration::from_millis(duration_ms), 300).unwrap();
+            let decoded: Duration = GasWaitDuration::from(gas_config.calc_gas_wait()).into();
+            assert_eq!(decoded, Duration::from_millis(expected_ms));
+        }
+    }
 }
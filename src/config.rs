@@ -1,15 +1,25 @@
+use core::fmt::{Display, Formatter};
+use core::ops::RangeInclusive;
+use core::str::FromStr;
 use core::time::Duration;
 
-use log::warn;
-
 use crate::{
-    constants::{GAS_ARRAY_1, GAS_ARRAY_2, MAX_HEATER_TEMPERATURE, MAX_HEATER_WAIT_DURATION_MS},
-    data::CalibrationData,
+    bitfields::{GasWaitDuration, RawConfig},
+    constants::{
+        CYCLE_DURATION, GAS_ARRAY_1, GAS_ARRAY_2, GAS_RANGE_COUNT_HIGH, HEATER_CURRENT,
+        HUMIDITY_CURRENT, MAX_HEATER_TEMPERATURE, MAX_HEATER_WAIT_DURATION_MS, PRESSURE_CURRENT,
+        SUPPLY_VOLTAGE, TEMPERATURE_CURRENT,
+    },
+    data::{CalibrationData, MeasurementData},
+    error::{ConfigError, ParseError, SelfTestError},
+    logging::warn,
 };
 
 /// Use Primary if SDO connector of the sensor is connected to ground and Secondary if SDO is connected to Vin.
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum DeviceAddress {
+    #[default]
     Primary = 0x76,
     Secondary = 0x77,
 }
@@ -23,16 +33,69 @@ impl From<DeviceAddress> for u8 {
     }
 }
 
-impl Default for DeviceAddress {
+/// Retries failed i2c transfers, see [`Bme680::with_retry_policy`](crate::Bme680::with_retry_policy).
+///
+/// Only errors reported by the i2c bus (e.g. a NACK) are retried.
+/// Errors caused by the sensor state, like an unexpected chip id or a measurement timeout, are returned immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RetryPolicy {
+    /// Number of additional attempts after the first failed transfer. 0 disables retrying.
+    pub retries: u8,
+    /// Pause between two attempts in microseconds
+    pub delay_us: u32,
+}
+
+/// Values accepted as plausible by [`Bme680::self_test_with_bounds`](crate::Bme680::self_test_with_bounds).
+///
+/// The default covers the operating range of the datasheet: -40°C to 85°C, 300hPa to 1100hPa and 0% to 100%.
+/// Narrow or shift the ranges to the expected environment, e.g. a freezer or a heated enclosure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlausibleRanges {
+    /// Temperature in °C
+    pub temperature: RangeInclusive<f32>,
+    /// Pressure in Pa
+    pub pressure: RangeInclusive<f32>,
+    /// Relative humidity in %
+    pub humidity: RangeInclusive<f32>,
+}
+
+impl Default for PlausibleRanges {
     fn default() -> Self {
-        Self::Primary
+        Self {
+            temperature: -40.0..=85.0,
+            pressure: 30_000.0..=110_000.0,
+            humidity: 0.0..=100.0,
+        }
     }
 }
+
+impl PlausibleRanges {
+    /// Checks that temperature, pressure and humidity of the measurement are within the ranges.
+    /// The first value outside of its range is returned as error.
+    pub fn check(&self, data: &MeasurementData) -> Result<(), SelfTestError> {
+        if !self.temperature.contains(&data.temperature) {
+            return Err(SelfTestError::TemperatureOutOfRange(data.temperature));
+        }
+        if !self.pressure.contains(&data.pressure) {
+            return Err(SelfTestError::PressureOutOfRange(data.pressure));
+        }
+        if !self.humidity.contains(&data.humidity) {
+            return Err(SelfTestError::HumidityOutOfRange(data.humidity));
+        }
+        Ok(())
+    }
+}
+
+/// Sensor variant read from the variant_id register.
+/// It selects the formula used to calculate the gas resistance.
 // Variant_id
 // gas_low = 0
 // gas_high = 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Variant {
+    /// BME680
     GasLow = 0,
+    /// BME688
     GasHigh = 1,
 }
 impl From<u8> for Variant {
@@ -48,13 +111,28 @@ impl From<u8> for Variant {
 }
 
 impl Variant {
+    /// Number of valid gas_range values for this variant.
+    /// The BME680 formula looks up correction values for each range, the BME688 formula only shifts by the range
+    /// which is limited by the 4 bit gas_range field.
+    pub fn gas_range_count(&self) -> usize {
+        match self {
+            Self::GasLow => GAS_ARRAY_1.len(),
+            Self::GasHigh => GAS_RANGE_COUNT_HIGH,
+        }
+    }
+    /// Calculates the gas resistance in Ohms with the formula of the variant.
+    /// Returns None if the gas_range is out of bounds for the variant.
     pub fn calc_gas_resistance(
         &self,
         adc_gas: u16,
         range_switching_error: i8,
         gas_range: usize,
-    ) -> f32 {
-        match self {
+    ) -> Option<f32> {
+        if gas_range >= self.gas_range_count() {
+            warn!("Gas range {gas_range} is out of bounds for variant {self:?}");
+            return None;
+        }
+        let gas_resistance = match self {
             Self::GasLow => {
                 let adc_gas = adc_gas as f32;
                 let gas_range_f = (1 << gas_range) as f32;
@@ -71,13 +149,18 @@ impl Variant {
                 var2 += 4096;
                 1000000. * var1 as f32 / var2 as f32
             }
-        }
+        };
+        Some(gas_resistance)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Power mode of the sensor.
+/// After a forced measurement the sensor returns to sleep mode by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SensorMode {
+    /// No measurements are performed
     Sleep,
+    /// A single measurement is performed
     Forced,
 }
 
@@ -89,12 +172,16 @@ impl From<SensorMode> for u8 {
         }
     }
 }
-impl From<u8> for SensorMode {
-    fn from(val: u8) -> Self {
+/// Decodes the mode bits of the ctrl_meas register.
+/// Returns the bits as error if they are neither sleep (0b00) nor forced (0b01) mode,
+/// e.g. a BME688 in parallel mode (0b10) or a register read as 0xFF on a glitching bus.
+impl TryFrom<u8> for SensorMode {
+    type Error = u8;
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
-            0 => SensorMode::Sleep,
-            1 => SensorMode::Forced,
-            invalid => panic!("Failed to read sensor mode. Received {invalid:b} possible values are 0b00(sleep) or 0b01(forced)"),
+            0 => Ok(SensorMode::Sleep),
+            1 => Ok(SensorMode::Forced),
+            invalid => Err(invalid),
         }
     }
 }
@@ -110,13 +197,49 @@ pub struct GasConfig {
 impl Default for GasConfig {
     /// Defaults to 150ms heater duration and 300°C heater target temperature
     fn default() -> Self {
-        Self {
-            heater_duration: Duration::from_millis(150),
-            heater_target_temperature: 300,
-        }
+        Self::DEFAULT
     }
 }
 impl GasConfig {
+    /// Same as [`GasConfig::default`] but usable in const contexts.
+    pub const DEFAULT: Self = Self {
+        heater_duration: Duration::from_millis(150),
+        heater_target_temperature: 300,
+    };
+    /// Highest heater target temperature in °C, higher temperatures are capped.
+    pub const MAX_TEMPERATURE: u16 = MAX_HEATER_TEMPERATURE;
+    /// Longest heater duration the gas_wait register can hold (4032ms).
+    pub const MAX_DURATION: Duration = Duration::from_millis(MAX_HEATER_WAIT_DURATION_MS as u64);
+    /// Creates a new gas config.
+    ///
+    /// # Arguments
+    /// * `heater_duration` - How long the heater is kept at the target temperature before measuring.
+    ///   The maximum is [`GasConfig::MAX_DURATION`] (4032ms).
+    /// * `heater_target_temperature` - Target temperature in °C. Values above [`GasConfig::MAX_TEMPERATURE`] (400°C) are capped.
+    /// # Errors
+    /// Returns [`ConfigError::HeaterDurationTooLong`] if the heater duration exceeds the maximum.
+    pub fn new(
+        heater_duration: Duration,
+        heater_target_temperature: u16,
+    ) -> Result<Self, ConfigError> {
+        if heater_duration > Self::MAX_DURATION {
+            return Err(ConfigError::HeaterDurationTooLong);
+        }
+        Ok(Self {
+            heater_duration,
+            heater_target_temperature,
+        })
+    }
+    /// Heater target temperature in °C that is actually used, i.e. capped at 400°C.
+    pub fn effective_target_temperature(&self) -> u16 {
+        self.heater_target_temperature.min(Self::MAX_TEMPERATURE)
+    }
+    /// Heater duration that is actually used.
+    /// The duration is capped at 4032ms and rounded down to the resolution of the gas_wait register.
+    pub fn effective_heater_duration(&self) -> Duration {
+        GasWaitDuration::from(self.calc_gas_wait()).into()
+    }
+    /// Calculates the value of the gas_wait register for the heater duration.
     pub fn calc_gas_wait(&self) -> u8 {
         let mut duration = self.heater_duration.as_millis() as u16;
         let mut factor: u8 = 0;
@@ -132,20 +255,19 @@ impl GasConfig {
             duration as u8 + factor * 64
         }
     }
+    /// Calculates the value of the res_heat register for the heater target temperature.
+    /// The ambient temperature is given in °C.
     pub fn calc_res_heat(
         &self,
         calibration_data: &CalibrationData,
         ambient_temperature: i32,
     ) -> u8 {
-        // cap at 400°C
-        let target_temperature = if self.heater_target_temperature > MAX_HEATER_TEMPERATURE {
+        if self.heater_target_temperature > MAX_HEATER_TEMPERATURE {
             warn!(
                 "Specified heater target temperature higher than {MAX_HEATER_TEMPERATURE}°C. Setting to 400°C instead."  
           );
-            400u16
-        } else {
-            self.heater_target_temperature
-        };
+        }
+        let target_temperature = self.effective_target_temperature();
         let var1 = ((ambient_temperature * calibration_data.par_gh3 as i32) / 1000) * 256;
         let var2 = (calibration_data.par_gh1 as i32 + 784)
             * (((((calibration_data.par_gh2 as i32 + 154009) * target_temperature as i32 * 5)
@@ -160,6 +282,18 @@ impl GasConfig {
     }
 }
 
+impl Display for GasConfig {
+    /// Formats the heater settings as e.g. `300C/150ms`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}C/{}ms",
+            self.heater_target_temperature,
+            self.heater_duration.as_millis()
+        )
+    }
+}
+
 /// Used to set Sensor settings.
 /// All options not set by the builder are set to default values.
 ///
@@ -184,6 +318,15 @@ pub struct Configuration {
     pub humidity_oversampling: Option<Oversampling>,
     pub filter: Option<IIRFilter>,
     pub gas_config: Option<GasConfig>,
+    /// Padding in percent added to the calculated measurement duration before the sensor is polled for new data the first time.
+    pub delay_margin_percent: u8,
+    /// Offset in hundredths of a °C subtracted from the compensated temperature, e.g. 150 to correct 1.5°C
+    /// self-heating of the board. It is applied after the Bosch compensation and the corrected temperature is also
+    /// used to calculate the humidity and the heater target.
+    pub temperature_offset_centi: i16,
+    /// Number of measurements discarded after the configuration was applied, taken by the first measurement of the driver.
+    /// The first readings, especially the gas resistance, are often off until the heater control loop has settled.
+    pub warmup_discard: u8,
 }
 
 impl Default for Configuration {
@@ -195,22 +338,188 @@ impl Default for Configuration {
     /// Gas config:
     /// heating duration: 150ms,
     /// heater target temperature: 300°C
+    /// Delay margin: 0%
+    /// Temperature offset: 0°C
+    /// Warmup discard: 0
     fn default() -> Self {
-        Self {
-            temperature_oversampling: Some(Oversampling::By2),
-            pressure_oversampling: Some(Oversampling::By16),
-            humidity_oversampling: Some(Oversampling::By1),
-            filter: Some(IIRFilter::Coeff1),
-            gas_config: Some(GasConfig::default()),
-        }
+        Self::DEFAULT
     }
 }
 impl Configuration {
+    /// Same as [`Configuration::default`] but usable in const contexts.
+    /// ```rust
+    /// # use bosch_bme680::Configuration;
+    /// static CONFIG: Configuration = Configuration::DEFAULT;
+    /// ```
+    pub const DEFAULT: Self = Self {
+        temperature_oversampling: Some(Oversampling::By2),
+        pressure_oversampling: Some(Oversampling::By16),
+        humidity_oversampling: Some(Oversampling::By1),
+        filter: Some(IIRFilter::Coeff1),
+        gas_config: Some(GasConfig::DEFAULT),
+        delay_margin_percent: 0,
+        temperature_offset_centi: 0,
+        warmup_discard: 0,
+    };
+    /// Preset for weather monitoring with a measurement every few seconds or minutes.
+    /// Temperature, pressure and humidity are measured with oversampling x1 and without the IIR filter,
+    /// the gas measurement is disabled.
+    pub const WEATHER: Self = Self {
+        temperature_oversampling: Some(Oversampling::By1),
+        pressure_oversampling: Some(Oversampling::By1),
+        humidity_oversampling: Some(Oversampling::By1),
+        filter: Some(IIRFilter::Coeff0),
+        gas_config: None,
+        ..Self::DEFAULT
+    };
+    /// Preset for gas measurements, e.g. for air quality estimates.
+    /// Temperature, pressure and humidity are measured with oversampling x2, x1 and x1 to keep the conversion short
+    /// and the heater is run at 320°C for 150ms.
+    pub const GAS_SCAN: Self = Self {
+        temperature_oversampling: Some(Oversampling::By2),
+        pressure_oversampling: Some(Oversampling::By1),
+        humidity_oversampling: Some(Oversampling::By1),
+        filter: Some(IIRFilter::Coeff0),
+        gas_config: Some(GasConfig {
+            heater_duration: Duration::from_millis(150),
+            heater_target_temperature: 320,
+        }),
+        ..Self::DEFAULT
+    };
+    /// Preset with the lowest energy per measurement that still reports temperature and humidity.
+    /// The pressure and gas measurement are skipped and the IIR filter is off.
+    pub const LOW_POWER: Self = Self {
+        temperature_oversampling: Some(Oversampling::By1),
+        pressure_oversampling: Some(Oversampling::Skipped),
+        humidity_oversampling: Some(Oversampling::By1),
+        filter: Some(IIRFilter::Coeff0),
+        gas_config: None,
+        ..Self::DEFAULT
+    };
+    /// Returns the config registers 0x71 to 0x75 this configuration would be written as, without a sensor attached.
+    /// Settings that are None are taken from `base`, which should contain the current register values of the sensor.
+    /// The heater registers of the gas config are not part of the result.
+    pub fn to_raw_registers(&self, base: [u8; 5]) -> [u8; 5] {
+        let mut raw_config = RawConfig(base);
+        raw_config.apply_config(self);
+        raw_config.0
+    }
+    /// Rough estimate of the energy a single forced measurement with this configuration takes in µJ.
+    ///
+    /// Assumes a supply voltage of 3.3V and the typical supply currents from the datasheet:
+    /// 350µA while measuring the temperature, 714µA for the pressure, 340µA for the humidity
+    /// and 12mA while the gas heater is on.
+    /// Every oversampling cycle takes 1.963ms and the heater is on for the effective heater duration.
+    /// Oversampling settings that are None are counted as skipped. The sleep current and the short
+    /// switching phases between the measurements are neglected.
+    pub fn estimated_energy_uj(&self) -> u32 {
+        let cycles = |oversampling: &Option<Oversampling>| {
+            oversampling.as_ref().map_or(0, Oversampling::cycles) as u64
+        };
+        // charge in pico coulomb (µA * µs)
+        let mut charge = CYCLE_DURATION as u64
+            * (cycles(&self.temperature_oversampling) * TEMPERATURE_CURRENT
+                + cycles(&self.pressure_oversampling) * PRESSURE_CURRENT
+                + cycles(&self.humidity_oversampling) * HUMIDITY_CURRENT);
+        if let Some(gas_config) = &self.gas_config {
+            charge += gas_config.effective_heater_duration().as_micros() as u64 * HEATER_CURRENT;
+        }
+        // pico coulomb * millivolt = femto joule
+        (charge * SUPPLY_VOLTAGE / 1_000_000_000) as u32
+    }
+    /// Number of measurements to discard after enabling or changing the IIR filter
+    /// before the filtered temperature and pressure can be trusted.
+    ///
+    /// The filter mixes every new sample with the previous output, `(previous * c + new) / (c + 1)` for coefficient c,
+    /// so it takes a number of samples to follow a change. The returned number is how many samples it takes
+    /// until at least 75% of a step has been followed, the step response the datasheet uses to specify the filter.
+    /// In forced mode every measurement is one sample. Returns 0 if the filter is off or None.
+    pub fn iir_settling_samples(&self) -> u8 {
+        self.filter.as_ref().map_or(0, IIRFilter::settling_samples)
+    }
+    /// Checks the configuration for contradictory settings.
+    /// The registers are checked as they would be written on top of the sensor's reset values.
+    ///
+    /// Enabling the gas measurement without measuring the humidity is rejected as well,
+    /// since gas based air quality estimates need the humidity.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let humidity_skipped = matches!(
+            self.humidity_oversampling,
+            None | Some(Oversampling::Skipped)
+        );
+        if self.gas_config.is_some() && humidity_skipped {
+            return Err(ConfigError::GasWithoutHumidity);
+        }
+        RawConfig(self.to_raw_registers([0; 5])).validate()
+    }
     pub fn builder() -> ConfigBuilder {
         ConfigBuilder {
             config: Configuration::default(),
         }
     }
+    /// Temperature offset in °C, see [`Configuration::temperature_offset_centi`].
+    pub fn temperature_offset(&self) -> f32 {
+        self.temperature_offset_centi as f32 / 100.
+    }
+    /// Compares the configuration field by field with `other`, e.g. to detect that the sensor lost its configuration.
+    pub fn diff(&self, other: &Configuration) -> ConfigDiff {
+        ConfigDiff {
+            temperature_oversampling: self.temperature_oversampling
+                != other.temperature_oversampling,
+            pressure_oversampling: self.pressure_oversampling != other.pressure_oversampling,
+            humidity_oversampling: self.humidity_oversampling != other.humidity_oversampling,
+            filter: self.filter != other.filter,
+            gas_config: self.gas_config != other.gas_config,
+            delay_margin_percent: self.delay_margin_percent != other.delay_margin_percent,
+            temperature_offset_centi: self.temperature_offset_centi
+                != other.temperature_offset_centi,
+            warmup_discard: self.warmup_discard != other.warmup_discard,
+        }
+    }
+}
+
+/// Result of [`Configuration::diff`]. Every field is true if the setting differs between the two configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConfigDiff {
+    pub temperature_oversampling: bool,
+    pub pressure_oversampling: bool,
+    pub humidity_oversampling: bool,
+    pub filter: bool,
+    pub gas_config: bool,
+    pub delay_margin_percent: bool,
+    pub temperature_offset_centi: bool,
+    pub warmup_discard: bool,
+}
+impl ConfigDiff {
+    /// Returns true if no setting differs.
+    pub fn is_empty(&self) -> bool {
+        *self == ConfigDiff::default()
+    }
+}
+impl Display for Configuration {
+    /// Formats the sensor settings in a compact form, e.g. `T:x2 P:x16 H:x1 IIR:1 gas:300C/150ms`.
+    /// Settings that are None are shown as `-`, a disabled gas measurement as `gas:off`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let oversamplings = [
+            ("T", &self.temperature_oversampling),
+            ("P", &self.pressure_oversampling),
+            ("H", &self.humidity_oversampling),
+        ];
+        for (name, oversampling) in oversamplings {
+            match oversampling {
+                Some(oversampling) => write!(f, "{name}:{oversampling} ")?,
+                None => write!(f, "{name}:- ")?,
+            }
+        }
+        match &self.filter {
+            Some(filter) => write!(f, "IIR:{} ", filter.coefficient())?,
+            None => f.write_str("IIR:- ")?,
+        }
+        match &self.gas_config {
+            Some(gas_config) => write!(f, "gas:{gas_config}"),
+            None => f.write_str("gas:off"),
+        }
+    }
 }
 pub struct ConfigBuilder {
     config: Configuration,
@@ -236,14 +545,34 @@ impl ConfigBuilder {
         self.config.gas_config = gas_config;
         self
     }
+    pub fn delay_margin_percent(mut self, delay_margin_percent: u8) -> Self {
+        self.config.delay_margin_percent = delay_margin_percent;
+        self
+    }
+    /// Offset in hundredths of a °C subtracted from the measured temperature,
+    /// see [`Configuration::temperature_offset_centi`].
+    pub fn temperature_offset_centi(mut self, temperature_offset_centi: i16) -> Self {
+        self.config.temperature_offset_centi = temperature_offset_centi;
+        self
+    }
+    /// Number of measurements to discard after the configuration was applied, see [`Configuration::warmup_discard`].
+    pub fn warmup_discard(mut self, warmup_discard: u8) -> Self {
+        self.config.warmup_discard = warmup_discard;
+        self
+    }
     pub fn build(self) -> Configuration {
         self.config
     }
+    /// Same as [`ConfigBuilder::build`] but checks the configuration with [`Configuration::validate`] first.
+    pub fn try_build(self) -> Result<Configuration, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
 }
 /// Oversampling settings for temperature, humidity, pressure.
 /// Skipping means no measurment will be taken, which is not recommended for the temperature
 /// as it's needed to calculate the adjusted values for hummidiy and pressure.
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Oversampling {
     Skipped,
     By1,
@@ -264,6 +593,30 @@ impl Oversampling {
         }
     }
 }
+impl FromStr for Oversampling {
+    type Err = ParseError;
+    /// Accepts `skip`, `x1`, `x2`, `x4`, `x8` and `x16`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skipped),
+            "x1" => Ok(Self::By1),
+            "x2" => Ok(Self::By2),
+            "x4" => Ok(Self::By4),
+            "x8" => Ok(Self::By8),
+            "x16" => Ok(Self::By16),
+            _ => Err(ParseError),
+        }
+    }
+}
+impl Display for Oversampling {
+    /// Uses the same names as [`Oversampling::from_str`], i.e. `skip` or `x1` through `x16`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Skipped => f.write_str("skip"),
+            oversampling => write!(f, "x{}", oversampling.cycles()),
+        }
+    }
+}
 impl From<u8> for Oversampling {
     fn from(val: u8) -> Self {
         match val {
@@ -291,7 +644,7 @@ impl From<Oversampling> for u8 {
 }
 
 /// IIR filter control only applies to temperature and pressure data.
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum IIRFilter {
     Coeff0,
     Coeff1,
@@ -302,6 +655,53 @@ pub enum IIRFilter {
     Coeff63,
     Coeff127,
 }
+impl IIRFilter {
+    /// Filter coefficient, i.e. 0, 1, 3, 7, 15, 31, 63 or 127.
+    /// Larger coefficients smooth stronger but take more samples to follow a change.
+    pub fn coefficient(&self) -> u8 {
+        match self {
+            Self::Coeff0 => 0,
+            Self::Coeff1 => 1,
+            Self::Coeff3 => 3,
+            Self::Coeff7 => 7,
+            Self::Coeff15 => 15,
+            Self::Coeff31 => 31,
+            Self::Coeff63 => 63,
+            Self::Coeff127 => 127,
+        }
+    }
+    // Number of samples until the filtered value has followed at least 75% of a step, like in the datasheet.
+    // The filter computes (previous * c + new) / (c + 1), so this is the smallest n with (c / (c + 1))^n <= 0.25.
+    fn settling_samples(&self) -> u8 {
+        match self {
+            Self::Coeff0 => 0,
+            Self::Coeff1 => 2,
+            Self::Coeff3 => 5,
+            Self::Coeff7 => 11,
+            Self::Coeff15 => 22,
+            Self::Coeff31 => 44,
+            Self::Coeff63 => 89,
+            Self::Coeff127 => 177,
+        }
+    }
+}
+impl FromStr for IIRFilter {
+    type Err = ParseError;
+    /// Accepts `off` or `coeff0` and `coeff1` through `coeff127`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" | "coeff0" => Ok(Self::Coeff0),
+            "coeff1" => Ok(Self::Coeff1),
+            "coeff3" => Ok(Self::Coeff3),
+            "coeff7" => Ok(Self::Coeff7),
+            "coeff15" => Ok(Self::Coeff15),
+            "coeff31" => Ok(Self::Coeff31),
+            "coeff63" => Ok(Self::Coeff63),
+            "coeff127" => Ok(Self::Coeff127),
+            _ => Err(ParseError),
+        }
+    }
+}
 impl From<u8> for IIRFilter {
     fn from(value: u8) -> Self {
         match value {
@@ -332,7 +732,7 @@ impl From<IIRFilter> for u8 {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum HeaterProfile {
     Profile0,
     Profile1,
@@ -384,16 +784,23 @@ mod config_tests {
     extern crate std;
     use std::time::Duration;
 
-    use crate::config::SensorMode;
+    use crate::config::{HeaterProfile, SensorMode};
+    use crate::data::MeasurementData;
+    use crate::error::{ConfigError, ParseError, SelfTestError};
 
-    use super::GasConfig;
+    use super::{
+        ConfigDiff, Configuration, DeviceAddress, GasConfig, IIRFilter, Oversampling,
+        PlausibleRanges, Variant,
+    };
 
     #[test]
     fn test_sensor_mode() {
         let sleeping = 0u8;
         let forced = 1u8;
-        assert!(SensorMode::Sleep == sleeping.into());
-        assert!(SensorMode::Forced == forced.into());
+        assert_eq!(SensorMode::try_from(sleeping), Ok(SensorMode::Sleep));
+        assert_eq!(SensorMode::try_from(forced), Ok(SensorMode::Forced));
+        assert_eq!(SensorMode::try_from(0b10), Err(0b10));
+        assert_eq!(SensorMode::try_from(0b11), Err(0b11));
     }
     #[test]
     fn test_gas_config() {
@@ -405,4 +812,253 @@ mod config_tests {
         // taken from data sheet
         assert!(config.calc_gas_wait() == 0x59);
     }
+    #[test]
+    fn test_gas_config_new() {
+        let config = GasConfig::new(Duration::from_millis(4032), 300).unwrap();
+        assert_eq!(config.calc_gas_wait(), 0xff);
+        assert_eq!(
+            GasConfig::new(Duration::from_millis(4033), 300),
+            Err(ConfigError::HeaterDurationTooLong)
+        );
+        assert_eq!(
+            GasConfig::new(Duration::from_secs(10), 300),
+            Err(ConfigError::HeaterDurationTooLong)
+        );
+        assert!(GasConfig::new(GasConfig::MAX_DURATION, GasConfig::MAX_TEMPERATURE).is_ok());
+    }
+    #[test]
+    fn test_effective_gas_config() {
+        let config = GasConfig {
+            heater_duration: Duration::from_millis(100),
+            heater_target_temperature: 200,
+        };
+        // 0x59: 25ms * 4
+        assert_eq!(config.effective_heater_duration(), config.heater_duration);
+        assert_eq!(config.effective_target_temperature(), 200);
+        let config = GasConfig {
+            heater_duration: Duration::from_millis(4100),
+            heater_target_temperature: 450,
+        };
+        assert_eq!(
+            config.effective_heater_duration(),
+            Duration::from_millis(4032)
+        );
+        assert_eq!(config.effective_target_temperature(), 400);
+        // 150ms can't be represented exactly
+        assert_eq!(
+            GasConfig::default().effective_heater_duration(),
+            Duration::from_millis(148)
+        );
+    }
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!("skip".parse(), Ok(Oversampling::Skipped));
+        assert_eq!("x16".parse(), Ok(Oversampling::By16));
+        assert_eq!("x3".parse::<Oversampling>(), Err(ParseError));
+        assert_eq!("off".parse(), Ok(IIRFilter::Coeff0));
+        assert_eq!("coeff0".parse(), Ok(IIRFilter::Coeff0));
+        assert_eq!("coeff127".parse(), Ok(IIRFilter::Coeff127));
+        assert_eq!("coeff2".parse::<IIRFilter>(), Err(ParseError));
+    }
+    #[test]
+    fn test_filter_coefficient() {
+        assert_eq!(IIRFilter::Coeff0.coefficient(), 0);
+        assert_eq!(IIRFilter::Coeff7.coefficient(), 7);
+        assert_eq!(IIRFilter::Coeff127.coefficient(), 127);
+        for coefficient in [0, 1, 3, 7, 15, 31, 63, 127] {
+            let filter: IIRFilter = std::format!("coeff{coefficient}").parse().unwrap();
+            assert_eq!(filter.coefficient(), coefficient);
+        }
+    }
+    #[test]
+    fn test_iir_settling_samples() {
+        assert_eq!(Configuration::default().iir_settling_samples(), 2);
+        let config = Configuration::builder().filter(IIRFilter::Coeff15).build();
+        assert_eq!(config.iir_settling_samples(), 22);
+        let config = Configuration::builder().filter(IIRFilter::Coeff0).build();
+        assert_eq!(config.iir_settling_samples(), 0);
+    }
+    #[test]
+    fn test_device_address() {
+        let address = DeviceAddress::default();
+        assert_eq!(address, DeviceAddress::Primary);
+        assert_ne!(address, DeviceAddress::Secondary);
+        assert_eq!(u8::from(address), 0x76);
+    }
+
+    #[test]
+    fn test_to_raw_registers() {
+        let base = [0, 0, 0, 0, 0];
+        #[allow(clippy::unusual_byte_groupings)]
+        let expected = [0b000_1_0000, 0b0_0_000_001, 0, 0b010_101_00, 0b000_001_00];
+        assert_eq!(Configuration::default().to_raw_registers(base), expected);
+        // None values keep the base registers
+        let config = Configuration {
+            temperature_oversampling: None,
+            pressure_oversampling: None,
+            humidity_oversampling: None,
+            filter: None,
+            gas_config: None,
+            ..Configuration::default()
+        };
+        assert_eq!(config.to_raw_registers(expected), expected);
+    }
+    #[test]
+    fn test_builder_temperature_offset() {
+        assert_eq!(Configuration::builder().build().temperature_offset(), 0.);
+        let config = Configuration::builder()
+            .temperature_offset_centi(150)
+            .build();
+        assert_eq!(config.temperature_offset_centi, 150);
+        assert_eq!(config.temperature_offset(), 1.5);
+        // the integer offset keeps the configuration comparable with Eq
+        fn assert_eq_impl<T: Eq>() {}
+        assert_eq_impl::<Configuration>();
+    }
+    #[test]
+    fn test_try_build() {
+        let config = Configuration::builder().gas_config(None).try_build();
+        assert_eq!(
+            config,
+            Ok(Configuration::builder().gas_config(None).build())
+        );
+        assert!(Configuration::builder().try_build().is_ok());
+        let config = Configuration::builder()
+            .humidity_oversampling(Oversampling::Skipped)
+            .try_build();
+        assert_eq!(config, Err(ConfigError::GasWithoutHumidity));
+        let config = Configuration::builder()
+            .humidity_oversampling(Oversampling::Skipped)
+            .gas_config(None)
+            .try_build();
+        assert!(config.is_ok());
+    }
+    #[test]
+    fn test_estimated_energy() {
+        // T x2, P x16, H x1 and 148ms heater duration
+        assert_eq!(Configuration::default().estimated_energy_uj(), 5941);
+        let config = Configuration::builder().gas_config(None).build();
+        assert_eq!(config.estimated_energy_uj(), 80);
+        let config = Configuration {
+            temperature_oversampling: None,
+            pressure_oversampling: Some(Oversampling::Skipped),
+            humidity_oversampling: Some(Oversampling::Skipped),
+            gas_config: None,
+            ..Configuration::default()
+        };
+        assert_eq!(config.estimated_energy_uj(), 0);
+    }
+    #[test]
+    fn test_const_default() {
+        static CONFIG: Configuration = Configuration::DEFAULT;
+        assert_eq!(CONFIG, Configuration::default());
+        assert_eq!(CONFIG.gas_config, Some(GasConfig::default()));
+    }
+    #[test]
+    fn test_gas_resistance() {
+        // adc value, gas range, expected resistance
+        let gas_low = [(512, 0, 8000000.), (600, 5, 232818.17)];
+        for (adc, range, expected) in gas_low {
+            let gas_resistance = Variant::GasLow.calc_gas_resistance(adc, 0, range).unwrap();
+            assert!((gas_resistance - expected).abs() / expected < 1e-5);
+        }
+        let gas_high = [
+            (512, 0, 64000000.),
+            (600, 5, 1878899.1),
+            (1023, 15, 1421.2116),
+        ];
+        for (adc, range, expected) in gas_high {
+            let gas_resistance = Variant::GasHigh.calc_gas_resistance(adc, 0, range).unwrap();
+            assert!((gas_resistance - expected).abs() / expected < 1e-5);
+        }
+        assert!(Variant::GasLow.calc_gas_resistance(512, 0, 16).is_none());
+        assert!(Variant::GasHigh.calc_gas_resistance(512, 0, 16).is_none());
+    }
+    #[test]
+    fn test_config_diff() {
+        let config = Configuration::default();
+        assert!(config.diff(&config).is_empty());
+        let other = Configuration::builder()
+            .filter(IIRFilter::Coeff3)
+            .gas_config(None)
+            .build();
+        assert_eq!(
+            config.diff(&other),
+            ConfigDiff {
+                filter: true,
+                gas_config: true,
+                ..ConfigDiff::default()
+            }
+        );
+    }
+    #[test]
+    fn test_config_display() {
+        use std::string::ToString;
+        assert_eq!(
+            Configuration::default().to_string(),
+            "T:x2 P:x16 H:x1 IIR:1 gas:300C/150ms"
+        );
+        let config = Configuration {
+            temperature_oversampling: None,
+            humidity_oversampling: Some(Oversampling::Skipped),
+            gas_config: None,
+            ..Configuration::default()
+        };
+        assert_eq!(config.to_string(), "T:- P:x16 H:skip IIR:1 gas:off");
+    }
+    #[test]
+    fn test_plausible_ranges() {
+        let data = |temperature, pressure, humidity| MeasurementData {
+            temperature,
+            humidity,
+            pressure,
+            gas_resistance: None,
+            humidity_clamped: false,
+        };
+        let ranges = PlausibleRanges::default();
+        assert_eq!(ranges.check(&data(21.5, 101_325.0, 45.0)), Ok(()));
+        assert_eq!(ranges.check(&data(-40.0, 30_000.0, 100.0)), Ok(()));
+        assert_eq!(
+            ranges.check(&data(-41.0, 101_325.0, 45.0)),
+            Err(SelfTestError::TemperatureOutOfRange(-41.0))
+        );
+        assert_eq!(
+            ranges.check(&data(21.5, 120_000.0, 45.0)),
+            Err(SelfTestError::PressureOutOfRange(120_000.0))
+        );
+        let greenhouse = PlausibleRanges {
+            humidity: 40.0..=95.0,
+            ..PlausibleRanges::default()
+        };
+        assert_eq!(
+            greenhouse.check(&data(21.5, 101_325.0, 20.0)),
+            Err(SelfTestError::HumidityOutOfRange(20.0))
+        );
+    }
+    #[test]
+    fn test_presets() {
+        static PRESETS: [Configuration; 3] = [
+            Configuration::WEATHER,
+            Configuration::GAS_SCAN,
+            Configuration::LOW_POWER,
+        ];
+        for preset in &PRESETS {
+            assert_eq!(preset.validate(), Ok(()));
+        }
+        let energy = |config: &Configuration| config.estimated_energy_uj();
+        assert!(energy(&Configuration::LOW_POWER) < energy(&Configuration::WEATHER));
+        assert!(energy(&Configuration::WEATHER) < energy(&Configuration::GAS_SCAN));
+    }
+    #[test]
+    fn test_enums_are_copy_and_one_byte() {
+        fn assert_copy<T: Copy>() {}
+        assert_copy::<Oversampling>();
+        assert_copy::<IIRFilter>();
+        assert_copy::<HeaterProfile>();
+        assert_copy::<SensorMode>();
+        assert_eq!(core::mem::size_of::<Oversampling>(), 1);
+        assert_eq!(core::mem::size_of::<IIRFilter>(), 1);
+        assert_eq!(core::mem::size_of::<HeaterProfile>(), 1);
+        assert_eq!(core::mem::size_of::<SensorMode>(), 1);
+    }
 }
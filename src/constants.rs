@@ -40,6 +40,19 @@ pub const TPH_SWITCHING_DURATION: u32 = 477 * 4;
 pub const GAS_MEAS_DURATION: u32 = 477 * 5;
 pub const WAKEUP_DURATION: u32 = 1000; // 1ms
 
+// give up waiting for new data after this multiple of the expected measurement duration
+pub const MEASUREMENT_TIMEOUT_FACTOR: u32 = 3;
+// number of times the data registers are polled per expected measurement duration while waiting for new data
+pub const POLLS_PER_DELAY_PERIOD: u32 = 4;
+
+// typical supply currents in micro ampere from the datasheet, used to estimate the energy of a measurement
+pub const TEMPERATURE_CURRENT: u64 = 350;
+pub const PRESSURE_CURRENT: u64 = 714;
+pub const HUMIDITY_CURRENT: u64 = 340;
+pub const HEATER_CURRENT: u64 = 12_000;
+// assumed supply voltage in millivolt
+pub const SUPPLY_VOLTAGE: u64 = 3300;
+
 // using float values from the reference implementation
 pub const GAS_ARRAY_1: [f32; 16] = [
     0.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, -0.8, 0.0, 0.0, -0.2, -0.5, 0.0, -1.0, 0.0, 0.0,
@@ -47,6 +60,8 @@ pub const GAS_ARRAY_1: [f32; 16] = [
 pub const GAS_ARRAY_2: [f32; 16] = [
     0.0, 0.0, 0.0, 0.0, 0.1, 0.7, 0.0, -0.8, -0.1, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
 ];
+// gas_range is a 4 bit field
+pub const GAS_RANGE_COUNT_HIGH: usize = 16;
 
 #[cfg(test)]
 mod tests {
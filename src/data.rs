@@ -1,3 +1,9 @@
+use crate::bitfields::RawData;
+use crate::config::Variant;
+use crate::error::CalibrationError;
+use crate::i2c_helper::extract_calibration_data;
+
+/// Calibration parameters that were saved on the sensor during production.
 #[derive(Debug)]
 pub struct CalibrationData {
     // Temperature coefficients
@@ -29,25 +35,378 @@ pub struct CalibrationData {
     pub par_gh3: i8,
 
     // Other
+    /// Heater resistance range, used to calculate the res_heat register value
     pub res_heat_range: u8,
+    /// Heater resistance correction factor, used to calculate the res_heat register value
     pub res_heat_val: i8,
+    /// Range switching error measured during production (signed 4 bit value).
+    /// It corrects the gas resistance calculation of the BME680 (GasLow variant),
+    /// so two sensors with different values report different gas resistances for the same raw adc value.
+    /// Each step shifts the constant 1340 of the formula by 5. It isn't used for the BME688 (GasHigh variant).
     pub range_sw_err: i8,
 }
 
-/// Measurment data returned from the sensor
-#[derive(Debug)]
-pub struct MeasurmentData {
+impl CalibrationData {
+    /// Extracts the calibration parameters from the 42 raw calibration bytes, e.g. from a logged register dump.
+    /// The bytes are the registers 0x8A..=0xA0 followed by 0xE1..=0xEE and 0x00..=0x04.
+    pub fn from_raw_bytes(bytes: [u8; 42]) -> CalibrationData {
+        extract_calibration_data(bytes)
+    }
+    /// Checks for coefficients that can't come from a successful read.
+    ///
+    /// The data is considered corrupt if par_t1 or par_p1 is 0 or 0xFFFF, which is what a read
+    /// returning only 0x00 or 0xFF bytes (e.g. a floating bus) produces, or if par_h2 is 0.
+    /// This is called by [`Bme680::new`](crate::Bme680::new) after reading the calibration data.
+    pub fn validate(&self) -> Result<(), CalibrationError> {
+        if matches!(self.par_t1, 0 | u16::MAX) {
+            return Err(CalibrationError::TemperatureCoefficientInvalid);
+        }
+        if matches!(self.par_p1, 0 | u16::MAX) {
+            return Err(CalibrationError::PressureCoefficientInvalid);
+        }
+        // the humidity is proportional to par_h2, so 0 would clamp every reading to 0%
+        if self.par_h2 == 0 {
+            return Err(CalibrationError::HumidityCoefficientZero);
+        }
+        Ok(())
+    }
+}
+
+/// Old misspelled name of [`MeasurementData`], kept so existing code keeps compiling.
+#[deprecated(note = "renamed to MeasurementData")]
+pub type MeasurmentData = MeasurementData;
+
+/// Measurement data returned from the sensor
+#[derive(Debug, Default)]
+pub struct MeasurementData {
     /// Temperature in °C
     pub temperature: f32,
     /// Relative humidity in %
     pub humidity: f32,
-    /// Pressure in hPa
+    /// Pressure in Pa
     pub pressure: f32,
     /// Gas resistance in Ohms
     /// None if gas measurment is disabled or gas measurment hasn't finished in time according to the gas_measuring bit.
     pub gas_resistance: Option<f32>,
+    /// True if the calculated humidity was outside of 0% to 100% and had to be clamped.
+    /// This usually indicates a calibration or read problem.
+    pub humidity_clamped: bool,
+}
+
+impl MeasurementData {
+    /// Decodes a raw frame of the 15 data registers starting at 0x1D (e.g. captured from an i2c log)
+    /// without a connected sensor. No temperature offset is applied.
+    /// Returns None if the frame doesn't contain a finished measurement.
+    pub fn decode(
+        frame: &[u8; 15],
+        calibration_data: &CalibrationData,
+        variant: Variant,
+    ) -> Option<Self> {
+        let raw_data = RawData(frame);
+        if !raw_data.new_data() || raw_data.measuring() {
+            return None;
+        }
+        Some(Self::from_raw(&raw_data, calibration_data, &variant, 0.))
+    }
+    // compensates the raw adc values of a finished measurement
+    // The temperature offset in °C is subtracted from the compensated temperature and the corrected temperature
+    // is used to calculate the humidity. The pressure is compensated with the uncorrected sensor temperature.
+    pub(crate) fn from_raw<T: AsRef<[u8]>>(
+        raw_data: &RawData<T>,
+        calibration_data: &CalibrationData,
+        variant: &Variant,
+        temperature_offset: f32,
+    ) -> Self {
+        let (_, t_fine) = calculate_temperature(raw_data.temperature_adc().0, calibration_data);
+        let pressure = calculate_pressure(raw_data.pressure_adc().0, calibration_data, t_fine);
+        let t_fine = t_fine - temperature_offset * 5120.;
+        let temperature = t_fine / 5120.;
+        let (humidity, unclamped_humidity) =
+            calculate_humidity(raw_data.humidity_adc().0, calibration_data, t_fine);
+        let humidity_clamped = !(0. ..=100.).contains(&unclamped_humidity);
+        let gas_resistance = if raw_data.gas_valid() && !raw_data.gas_measuring() {
+            variant.calc_gas_resistance(
+                raw_data.gas_adc().0,
+                calibration_data.range_sw_err,
+                raw_data.gas_range() as usize,
+            )
+        } else {
+            None
+        };
+        MeasurementData {
+            temperature,
+            gas_resistance,
+            humidity,
+            pressure,
+            humidity_clamped,
+        }
+    }
+    /// Vapor pressure deficit in kPa, i.e. how much more water vapor the air could hold at the measured temperature.
+    ///
+    /// The saturation vapor pressure is calculated with the Tetens equation
+    /// `es = 0.6108kPa * exp(17.27 * T / (T + 237.3°C))` and the deficit is `es * (1 - RH / 100%)`.
+    pub fn vapor_pressure_deficit(&self) -> f32 {
+        let saturation_vapor_pressure =
+            0.6108 * libm::expf(17.27 * self.temperature / (self.temperature + 237.3));
+        saturation_vapor_pressure * (1. - self.humidity / 100.)
+    }
+    /// Difference of the measured pressure to the reference pressure in hPa, e.g. to an outdoor or a previous reading.
+    /// Positive if the measured pressure is higher than the reference. The [`MeasurementData::pressure`] stays in Pa.
+    pub fn relative_pressure(&self, reference_hpa: f32) -> f32 {
+        self.pressure / 100. - reference_hpa
+    }
+    /// Rates the measurement using the status flags returned by [`Bme680::measure_with_status`](crate::Bme680::measure_with_status).
+    ///
+    /// * [`Quality::Bad`] if temperature, pressure or humidity isn't a finite number or the humidity had to be clamped.
+    /// * [`Quality::Degraded`] if a gas conversion was returned while the heater hadn't reached its target temperature
+    ///   or the gas resistance isn't a finite number.
+    /// * [`Quality::Good`] otherwise, including measurements without a gas conversion.
+    pub fn quality(&self, status: &MeasurementStatus) -> Quality {
+        let finite =
+            self.temperature.is_finite() && self.pressure.is_finite() && self.humidity.is_finite();
+        if !finite || self.humidity_clamped {
+            return Quality::Bad;
+        }
+        // Option::is_none_or would need Rust 1.82
+        #[allow(clippy::unnecessary_map_or)]
+        let gas_finite = self.gas_resistance.map_or(true, f32::is_finite);
+        if (status.gas_valid && !status.heater_stable) || !gas_finite {
+            return Quality::Degraded;
+        }
+        Quality::Good
+    }
+    /// Converts the measurement to integers, e.g. for binary protocols or integer only pipelines.
+    /// Temperature and humidity are scaled by 1000, pressure and gas resistance are kept in Pa and Ohms.
+    /// Every value is rounded to the nearest integer, which is finer than the resolution of the sensor.
+    pub fn as_milli(&self) -> MilliMeasurement {
+        MilliMeasurement {
+            temperature: round(self.temperature * 1000.) as i32,
+            humidity: round(self.humidity * 1000.) as u32,
+            pressure: round(self.pressure) as u32,
+            gas_resistance: self.gas_resistance.map(|gas| round(gas) as u32),
+        }
+    }
+}
+
+/// How far a measurement can be trusted, see [`MeasurementData::quality`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// All values are plausible.
+    Good,
+    /// Temperature, pressure and humidity are fine but the gas resistance is questionable.
+    Degraded,
+    /// Temperature, pressure or humidity can't be used.
+    Bad,
+}
+
+/// Measurement data in integer units, see [`MeasurementData::as_milli`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MilliMeasurement {
+    /// Temperature in m°C
+    pub temperature: i32,
+    /// Relative humidity in m%
+    pub humidity: u32,
+    /// Pressure in Pa
+    pub pressure: u32,
+    /// Gas resistance in Ohms
+    pub gas_resistance: Option<u32>,
+}
+
+/// Measurement data with a stable memory layout, e.g. to hand measurements to C code.
+///
+/// Corresponds to the C struct
+/// `struct { float temperature; float humidity; float pressure; float gas_resistance; bool humidity_clamped; }`.
+/// The units are the same as in [`MeasurementData`]. A missing gas resistance is stored as NaN,
+/// so C consumers have to check it with `isnan` before using it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CMeasurement {
+    /// Temperature in °C
+    pub temperature: f32,
+    /// Relative humidity in %
+    pub humidity: f32,
+    /// Pressure in Pa
+    pub pressure: f32,
+    /// Gas resistance in Ohms or NaN if there is no gas measurement
+    pub gas_resistance: f32,
+    /// True if the calculated humidity had to be clamped to 0% to 100%
+    pub humidity_clamped: bool,
+}
+
+impl From<&MeasurementData> for CMeasurement {
+    fn from(data: &MeasurementData) -> Self {
+        CMeasurement {
+            temperature: data.temperature,
+            humidity: data.humidity,
+            pressure: data.pressure,
+            gas_resistance: data.gas_resistance.unwrap_or(f32::NAN),
+            humidity_clamped: data.humidity_clamped,
+        }
+    }
+}
+
+// f32::round isn't available in core
+fn round(value: f32) -> f32 {
+    if value < 0. {
+        ((value - 0.5) as i64) as f32
+    } else {
+        ((value + 0.5) as i64) as f32
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl MeasurementData {
+    /// Returns true if temperature, humidity, pressure and gas resistance differ by at most `tolerance`
+    /// and the humidity of both was clamped or not clamped.
+    /// The gas resistances only match if both are None or both are Some.
+    pub fn approx_eq(&self, other: &Self, tolerance: f32) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= tolerance;
+        let gas_resistance = match (self.gas_resistance, other.gas_resistance) {
+            (Some(a), Some(b)) => close(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        close(self.temperature, other.temperature)
+            && close(self.humidity, other.humidity)
+            && close(self.pressure, other.pressure)
+            && gas_resistance
+            && self.humidity_clamped == other.humidity_clamped
+    }
+}
+
+/// Result of polling the sensor with [`Bme680::poll_event`](crate::Bme680::poll_event).
+#[derive(Debug)]
+pub enum SensorEvent {
+    /// The measurement has finished, including the gas measurement if it's enabled.
+    DataReady(MeasurementData),
+    /// A measurement was triggered or temperature, pressure and humidity are still being measured.
+    Busy,
+    /// Temperature, pressure and humidity are done but the heater or the gas measurement is still running.
+    GasNotReady,
+    /// The sensor is idle without having new data, so the triggered measurement was lost,
+    /// e.g. because the sensor was reset. The next poll triggers a new measurement.
+    Timeout,
+}
+
+/// Misspelled name of [`MeasurementStatus`], kept as an alias like [`MeasurmentData`].
+#[deprecated(note = "renamed to MeasurementStatus")]
+pub type MeasurmentStatus = MeasurementStatus;
+
+/// Status flags of a measurement, decoded from the same registers as the measurement data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeasurementStatus {
+    /// True if new data was available
+    pub new_data: bool,
+    /// True if the sensor was still measuring
+    pub measuring: bool,
+    /// True if the gas measurement was still running
+    pub gas_measuring: bool,
+    /// Index of the heater profile used for the measurement (0 to 9)
+    pub gas_meas_index: u8,
+    /// True if a real gas conversion (i.e. not a dummy one) was returned
+    pub gas_valid: bool,
+    /// True if the heater reached its target temperature
+    pub heater_stable: bool,
+    /// ADC range of the gas resistance measurement
+    pub gas_range: u8,
+}
+
+impl<T: AsRef<[u8]>> From<&RawData<T>> for MeasurementStatus {
+    fn from(raw_data: &RawData<T>) -> Self {
+        Self {
+            new_data: raw_data.new_data(),
+            measuring: raw_data.measuring(),
+            gas_measuring: raw_data.gas_measuring(),
+            gas_meas_index: raw_data.gas_meas_index(),
+            gas_valid: raw_data.gas_valid(),
+            heater_stable: raw_data.heater_sable(),
+            gas_range: raw_data.gas_range(),
+        }
+    }
 }
 
+/// Intermediate values of the compensation, see [`Bme680::measure_debug`](crate::Bme680::measure_debug).
+#[cfg(feature = "debug-internals")]
+#[derive(Debug)]
+pub struct CompensationDebug {
+    /// Raw adc values, including the gas adc value and range the gas resistance is calculated from
+    pub field_data: FieldData,
+    /// Bosch's fine temperature value the pressure was compensated with, i.e. without the temperature offset
+    pub t_fine: f32,
+    /// Compensated humidity in % before it was clamped to 0% to 100%
+    pub humidity_unclamped: f32,
+    /// The compensated measurement
+    pub data: MeasurementData,
+}
+
+#[cfg(feature = "debug-internals")]
+impl CompensationDebug {
+    pub(crate) fn new<T: AsRef<[u8]>>(
+        raw_data: &RawData<T>,
+        calibration_data: &CalibrationData,
+        temperature_offset: f32,
+        data: MeasurementData,
+    ) -> Self {
+        let (_, t_fine) = calculate_temperature(raw_data.temperature_adc().0, calibration_data);
+        // the humidity is compensated with the offset corrected temperature
+        let (_, humidity_unclamped) = calculate_humidity(
+            raw_data.humidity_adc().0,
+            calibration_data,
+            t_fine - temperature_offset * 5120.,
+        );
+        Self {
+            field_data: FieldData::from(raw_data),
+            t_fine,
+            humidity_unclamped,
+            data,
+        }
+    }
+}
+
+/// Uncompensated content of the data registers 0x1D to 0x2B, e.g. to apply a custom compensation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldData {
+    /// True if new data is available (0x1D bit 7)
+    pub new_data: bool,
+    /// True if the gas measurement is still running (0x1D bit 6)
+    pub gas_measuring: bool,
+    /// True if the sensor is still measuring (0x1D bit 5)
+    pub measuring: bool,
+    /// Index of the heater profile used for the measurement (0x1D bits 3 to 0)
+    pub gas_meas_index: u8,
+    /// 20 bit pressure adc value (0x1F, 0x20 and 0x21 bits 7 to 4)
+    pub pressure_adc: u32,
+    /// 20 bit temperature adc value (0x22, 0x23 and 0x24 bits 7 to 4)
+    pub temperature_adc: u32,
+    /// 16 bit humidity adc value (0x25 and 0x26)
+    pub humidity_adc: u16,
+    /// 10 bit gas adc value (0x2A and 0x2B bits 7 to 6)
+    pub gas_adc: u16,
+    /// True if a real gas conversion (i.e. not a dummy one) was returned (0x2B bit 5)
+    pub gas_valid: bool,
+    /// True if the heater reached its target temperature (0x2B bit 4)
+    pub heater_stable: bool,
+    /// ADC range of the gas resistance measurement (0x2B bits 3 to 0)
+    pub gas_range: u8,
+}
+
+impl<T: AsRef<[u8]>> From<&RawData<T>> for FieldData {
+    fn from(raw_data: &RawData<T>) -> Self {
+        Self {
+            new_data: raw_data.new_data(),
+            gas_measuring: raw_data.gas_measuring(),
+            measuring: raw_data.measuring(),
+            gas_meas_index: raw_data.gas_meas_index(),
+            pressure_adc: raw_data.pressure_adc().0,
+            temperature_adc: raw_data.temperature_adc().0,
+            humidity_adc: raw_data.humidity_adc().0,
+            gas_adc: raw_data.gas_adc().0,
+            gas_valid: raw_data.gas_valid(),
+            heater_stable: raw_data.heater_sable(),
+            gas_range: raw_data.gas_range(),
+        }
+    }
+}
 
 pub fn calculate_temperature(adc_temp: u32, calibration_data: &CalibrationData) -> (f32, f32) {
     let temp_adc = adc_temp as f32;
@@ -88,7 +447,12 @@ pub fn calculate_pressure(adc_press: u32, calibration_data: &CalibrationData, t_
     calc_pres
 }
 
-pub fn calculate_humidity(adc_hum: u16, calibration_data: &CalibrationData, t_fine: f32) -> f32 {
+// returns the humidity clamped to 0% to 100% and the unclamped humidity
+pub fn calculate_humidity(
+    adc_hum: u16,
+    calibration_data: &CalibrationData,
+    t_fine: f32,
+) -> (f32, f32) {
     let adc_hum = adc_hum as f32;
     let temp_comp = t_fine / 5120.;
     let var1 = (adc_hum)
@@ -101,21 +465,25 @@ pub fn calculate_humidity(adc_hum: u16, calibration_data: &CalibrationData, t_fi
                 + ((calibration_data.par_h5 as f32 / 1048576.) * temp_comp * temp_comp)));
     let var3 = calibration_data.par_h6 as f32 / 16384.;
     let var4 = calibration_data.par_h7 as f32 / 2097152.;
-    let mut calc_hum = var2 + ((var3 + (var4 * temp_comp)) * var2 * var2);
-    calc_hum = calc_hum.clamp(0., 100.);
+    let unclamped_hum = var2 + ((var3 + (var4 * temp_comp)) * var2 * var2);
+    let calc_hum = unclamped_hum.clamp(0., 100.);
     // Reference implemetation uses this.
     // if calc_hum > 100. {
     //     calc_hum = 100.;
     // } else if calc_hum < 0. {
     //     calc_hum = 0.
     // }
-    calc_hum
+    (calc_hum, unclamped_hum)
 }
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision)] // reference values from the c implementation
 mod tests {
-    use approx::{assert_abs_diff_eq, assert_relative_eq, relative_eq};
-    use crate::data::{calculate_humidity, calculate_pressure, calculate_temperature, CalibrationData};
+    use approx::assert_abs_diff_eq;
+    use crate::bitfields::RawData;
+    use crate::config::Variant;
+    use crate::data::{calculate_humidity, calculate_pressure, calculate_temperature, CalibrationData, CMeasurement, MeasurementData, MeasurementStatus, Quality};
+    use crate::error::CalibrationError;
 
     static CALIBRATION_DATA: CalibrationData = CalibrationData {
         par_t1: 25942,
@@ -146,8 +514,173 @@ mod tests {
         range_sw_err: 0,
     };
 
-
-
+    #[test]
+    fn test_temperature_offset() {
+        let mut frame = [0u8; 15];
+        // new data
+        frame[0] = 0b1000_0000;
+        // temperature adc 482062
+        frame[5..8].copy_from_slice(&[0x75, 0xB0, 0xE0]);
+        // humidity adc 25537
+        frame[8..10].copy_from_slice(&[0x63, 0xC1]);
+        let raw_data = RawData(frame);
+        let data = MeasurementData::from_raw(&raw_data, &CALIBRATION_DATA, &Variant::GasLow, 0.);
+        let corrected =
+            MeasurementData::from_raw(&raw_data, &CALIBRATION_DATA, &Variant::GasLow, 2.);
+        assert_abs_diff_eq!(data.temperature, 21.295866, epsilon = 0.0001);
+        assert_abs_diff_eq!(corrected.temperature, data.temperature - 2., epsilon = 0.0001);
+        // humidity is compensated with the corrected temperature
+        assert!(corrected.humidity != data.humidity);
+        assert_abs_diff_eq!(corrected.pressure, data.pressure);
+    }
+    #[test]
+    fn test_humidity_clamped() {
+        let mut frame = [0u8; 15];
+        frame[0] = 0b1000_0000;
+        // temperature adc 482062
+        frame[5..8].copy_from_slice(&[0x75, 0xB0, 0xE0]);
+        // humidity adc 25537
+        frame[8..10].copy_from_slice(&[0x63, 0xC1]);
+        let data = MeasurementData::from_raw(&RawData(frame), &CALIBRATION_DATA, &Variant::GasLow, 0.);
+        assert!(!data.humidity_clamped);
+        // humidity adc 0xffff
+        frame[8..10].copy_from_slice(&[0xff, 0xff]);
+        let data = MeasurementData::from_raw(&RawData(frame), &CALIBRATION_DATA, &Variant::GasLow, 0.);
+        assert!(data.humidity_clamped);
+        assert_abs_diff_eq!(data.humidity, 100.);
+    }
+    #[cfg(feature = "test-utils")]
+    #[test]
+    fn test_approx_eq() {
+        let data = MeasurementData {
+            temperature: 21.3,
+            humidity: 40.,
+            pressure: 1013.25,
+            gas_resistance: Some(50000.),
+            humidity_clamped: false,
+        };
+        let other = MeasurementData {
+            temperature: 21.35,
+            ..data
+        };
+        assert!(data.approx_eq(&other, 0.1));
+        assert!(!data.approx_eq(&other, 0.01));
+        let no_gas = MeasurementData {
+            gas_resistance: None,
+            ..data
+        };
+        assert!(!data.approx_eq(&no_gas, 0.1));
+        assert!(no_gas.approx_eq(&no_gas, 0.));
+    }
+    #[test]
+    fn test_as_milli() {
+        let data = MeasurementData {
+            temperature: -1.2346,
+            humidity: 40.5,
+            pressure: 100873.4,
+            gas_resistance: Some(50123.6),
+            humidity_clamped: false,
+        };
+        let milli = data.as_milli();
+        assert_eq!(milli.temperature, -1235);
+        assert_eq!(milli.humidity, 40500);
+        assert_eq!(milli.pressure, 100873);
+        assert_eq!(milli.gas_resistance, Some(50124));
+        let no_gas = MeasurementData {
+            temperature: 21.2954,
+            gas_resistance: None,
+            ..data
+        };
+        assert_eq!(no_gas.as_milli().temperature, 21295);
+        assert_eq!(no_gas.as_milli().gas_resistance, None);
+    }
+    #[test]
+    fn test_vapor_pressure_deficit() {
+        let data = MeasurementData {
+            temperature: 25.,
+            humidity: 50.,
+            pressure: 100000.,
+            gas_resistance: None,
+            humidity_clamped: false,
+        };
+        assert_abs_diff_eq!(data.vapor_pressure_deficit(), 1.5839, epsilon = 0.0001);
+        let saturated = MeasurementData {
+            humidity: 100.,
+            ..data
+        };
+        assert_abs_diff_eq!(saturated.vapor_pressure_deficit(), 0.);
+    }
+    #[test]
+    fn test_relative_pressure() {
+        let data = MeasurementData {
+            temperature: 21.5,
+            humidity: 40.5,
+            pressure: 100873.4,
+            gas_resistance: None,
+            humidity_clamped: false,
+        };
+        assert_abs_diff_eq!(data.relative_pressure(1013.25), -4.516, epsilon = 0.001);
+        assert_abs_diff_eq!(data.relative_pressure(1000.), 8.734, epsilon = 0.001);
+    }
+    #[test]
+    fn test_quality() {
+        let data = MeasurementData {
+            temperature: 21.5,
+            humidity: 40.5,
+            pressure: 100873.4,
+            gas_resistance: Some(50123.6),
+            humidity_clamped: false,
+        };
+        let status = MeasurementStatus {
+            new_data: true,
+            measuring: false,
+            gas_measuring: false,
+            gas_meas_index: 0,
+            gas_valid: true,
+            heater_stable: true,
+            gas_range: 3,
+        };
+        assert_eq!(data.quality(&status), Quality::Good);
+        let unstable = MeasurementStatus { heater_stable: false, ..status };
+        assert_eq!(data.quality(&unstable), Quality::Degraded);
+        let no_gas = MeasurementStatus { gas_valid: false, heater_stable: false, ..status };
+        assert_eq!(MeasurementData { gas_resistance: None, ..data }.quality(&no_gas), Quality::Good);
+        assert_eq!(MeasurementData { humidity_clamped: true, ..data }.quality(&status), Quality::Bad);
+        assert_eq!(MeasurementData { pressure: f32::NAN, ..data }.quality(&status), Quality::Bad);
+    }
+    #[test]
+    fn test_c_measurement() {
+        let data = MeasurementData {
+            temperature: 21.5,
+            humidity: 40.5,
+            pressure: 100873.4,
+            gas_resistance: Some(50123.6),
+            humidity_clamped: false,
+        };
+        let c_data = CMeasurement::from(&data);
+        assert_eq!(c_data.temperature, 21.5);
+        assert_eq!(c_data.gas_resistance, 50123.6);
+        let no_gas = MeasurementData {
+            gas_resistance: None,
+            ..data
+        };
+        assert!(CMeasurement::from(&no_gas).gas_resistance.is_nan());
+    }
+    #[test]
+    fn test_decode() {
+        let mut frame = [0u8; 15];
+        // temperature adc 482062
+        frame[5..8].copy_from_slice(&[0x75, 0xB0, 0xE0]);
+        // no new data
+        assert!(MeasurementData::decode(&frame, &CALIBRATION_DATA, Variant::GasLow).is_none());
+        // new data but still measuring
+        frame[0] = 0b1010_0000;
+        assert!(MeasurementData::decode(&frame, &CALIBRATION_DATA, Variant::GasLow).is_none());
+        frame[0] = 0b1000_0000;
+        let data = MeasurementData::decode(&frame, &CALIBRATION_DATA, Variant::GasLow).unwrap();
+        assert_abs_diff_eq!(data.temperature, 21.295866, epsilon = 0.0001);
+        assert!(data.gas_resistance.is_none());
+    }
     #[test]
     fn test_calc_temp() {
         // Calc_temp: temp_adc: 482062, calc_temp: 21.295866, tfine: 109034.835938
@@ -186,7 +719,7 @@ mod tests {
             (25549,59.537392,109531.328125),
         ];
         for (hum_adc, actual_hum, tfine) in pairs {
-            let calc_hum = calculate_humidity(hum_adc, &CALIBRATION_DATA, tfine);
+            let (calc_hum, _) = calculate_humidity(hum_adc, &CALIBRATION_DATA, tfine);
             assert_abs_diff_eq!(calc_hum, actual_hum);
         }
 
@@ -210,4 +743,23 @@ mod tests {
             assert_abs_diff_eq!(calc_press, actual_press);
         }
     }
+    #[test]
+    fn test_validate_calibration() {
+        assert_eq!(CALIBRATION_DATA.validate(), Ok(()));
+        assert_eq!(
+            CalibrationData::from_raw_bytes([0; 42]).validate(),
+            Err(CalibrationError::TemperatureCoefficientInvalid)
+        );
+        assert_eq!(
+            CalibrationData::from_raw_bytes([0xFF; 42]).validate(),
+            Err(CalibrationError::TemperatureCoefficientInvalid)
+        );
+        let mut bytes = [0xFF; 42];
+        // par_t1 is stored in 0xE9/0xEA
+        bytes[31] = 0x12;
+        assert_eq!(
+            CalibrationData::from_raw_bytes(bytes).validate(),
+            Err(CalibrationError::PressureCoefficientInvalid)
+        );
+    }
 }
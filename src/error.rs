@@ -1,37 +1,255 @@
 use core::fmt::Formatter;
 use embedded_hal::i2c::{I2c, SevenBitAddress};
 
+use crate::constants::{CHIP_ID, MAX_HEATER_WAIT_DURATION_MS};
 
 /// All possible errors
 pub enum BmeError<I2C>
 where
-    I2C: I2c<SevenBitAddress>
+    I2C: I2c<SevenBitAddress>,
 {
-    /// Error during I2C write operation.
-    WriteError(I2C::Error),
-    /// Error during I2C WriteRead operation.
-    WriteReadError(I2C::Error),
+    /// Error during I2C write operation while writing the register `addr`.
+    WriteError { addr: u8, source: I2C::Error },
+    /// Error during I2C WriteRead operation while reading the register `addr`
+    /// (the first register if several registers are read at once).
+    WriteReadError { addr: u8, source: I2C::Error },
     /// Got an unexpected ChipId during sensor initalization.
     UnexpectedChipId(u8),
+    /// The chip id read as 0x00 or 0xFF during sensor initalization.
+    /// This usually means the sensor is absent or unpowered and the bus lines are just pulled up or down.
+    SensorNotResponding(u8),
     /// After running the measurment the sensor blocks until the 'new data bit' of the sensor is set.
-    /// Should this take more than 5 tries an error is returned instead of incorrect data.
-    MeasuringTimeOut,
+    /// Should this take more than 3 times the expected measurement duration an error is returned instead of incorrect data.
+    /// `attempts` is the number of times the data registers were read before giving up.
+    MeasuringTimeOut { attempts: u8 },
+    /// The measurement timed out and the sensor was in sleep mode without new data afterwards,
+    /// so the write triggering the forced mode didn't take effect (e.g. it was dropped on the bus).
+    ForcedModeNotEntered,
+    /// A register didn't contain the written value when reading it back.
+    /// Only returned if write verification is enabled.
+    WriteVerificationFailed { addr: u8, wrote: u8, read: u8 },
+    /// The calibration data read from the sensor is implausible, most likely because the read failed.
+    InvalidCalibration(CalibrationError),
+    /// The mode bits of the ctrl_meas register (0x74) are neither sleep nor forced mode.
+    /// Contains the read bits, e.g. 0b10 for a BME688 in parallel mode or 0b11 if the register read as 0xFF.
+    UnknownSensorMode(u8),
+    /// A measurement taken by the self test was outside of the plausible ranges.
+    SelfTestFailed(SelfTestError),
 }
 
 impl<I2C> core::fmt::Debug for BmeError<I2C>
 where
-    I2C: I2c<SevenBitAddress>
+    I2C: I2c<SevenBitAddress>,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
         match self {
-            BmeError::WriteReadError(e) => f.debug_tuple("WriteReadError").field(e).finish(),
-            BmeError::WriteError(e) => f.debug_tuple("WriteError").field(e).finish(),
+            BmeError::WriteReadError { addr, source } => f
+                .debug_struct("WriteReadError")
+                .field("addr", &format_args!("{addr:#04x}"))
+                .field("source", source)
+                .finish(),
+            BmeError::WriteError { addr, source } => f
+                .debug_struct("WriteError")
+                .field("addr", &format_args!("{addr:#04x}"))
+                .field("source", source)
+                .finish(),
             BmeError::UnexpectedChipId(chip_id) => f
-                .debug_tuple("Got unimplemented chip id: ")
-                .field(chip_id)
+                .debug_struct("UnexpectedChipId")
+                .field("chip_id", &format_args!("{chip_id:#04x}"))
+                .field("expected", &format_args!("{CHIP_ID:#04x}"))
+                .finish(),
+            BmeError::SensorNotResponding(chip_id) => f
+                .debug_struct("SensorNotResponding")
+                .field("chip_id", &format_args!("{chip_id:#04x}"))
+                .finish(),
+            BmeError::MeasuringTimeOut { attempts } => f
+                .debug_struct("Timed out while waiting for new measurement values. Either no new data or the sensor took unexpectedly long to finish measuring.")
+                .field("attempts", attempts)
                 .finish(),
-            BmeError::MeasuringTimeOut => f
-                .debug_tuple("Timed out while waiting for new measurement values. Either no new data or the sensor took unexpectedly long to finish measuring.").finish()
+            BmeError::ForcedModeNotEntered => f.write_str("ForcedModeNotEntered"),
+            BmeError::WriteVerificationFailed { addr, wrote, read } => f
+                .debug_struct("WriteVerificationFailed")
+                .field("addr", &format_args!("{addr:#04x}"))
+                .field("wrote", &format_args!("{wrote:#010b}"))
+                .field("read", &format_args!("{read:#010b}"))
+                .finish(),
+            BmeError::InvalidCalibration(e) => {
+                f.debug_tuple("InvalidCalibration").field(e).finish()
+            }
+            BmeError::UnknownSensorMode(mode) => f
+                .debug_struct("UnknownSensorMode")
+                .field("mode", &format_args!("{mode:#04b}"))
+                .finish(),
+            BmeError::SelfTestFailed(e) => f.debug_tuple("SelfTestFailed").field(e).finish(),
+        }
+    }
+}
+
+impl<I2C> core::fmt::Display for BmeError<I2C>
+where
+    I2C: I2c<SevenBitAddress>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BmeError::WriteReadError { addr, source } => {
+                write!(
+                    f,
+                    "i2c write read of register {addr:#04x} failed: {source:?}"
+                )
+            }
+            BmeError::WriteError { addr, source } => {
+                write!(f, "i2c write of register {addr:#04x} failed: {source:?}")
+            }
+            BmeError::UnexpectedChipId(chip_id) => {
+                write!(
+                    f,
+                    "unexpected chip id {chip_id:#04x} (expected {CHIP_ID:#04x})"
+                )
+            }
+            BmeError::SensorNotResponding(chip_id) => write!(
+                f,
+                "sensor not responding (chip id read as {chip_id:#04x}), check wiring and power"
+            ),
+            BmeError::MeasuringTimeOut { attempts } => write!(
+                f,
+                "timed out while waiting for new measurement values after {attempts} reads"
+            ),
+            BmeError::ForcedModeNotEntered => {
+                f.write_str("sensor didn't enter forced mode, no measurement was started")
+            }
+            BmeError::WriteVerificationFailed { addr, wrote, read } => write!(
+                f,
+                "register {addr:#04x} read back {read:#010b} after writing {wrote:#010b}"
+            ),
+            BmeError::InvalidCalibration(e) => write!(f, "invalid calibration data: {e}"),
+            BmeError::UnknownSensorMode(mode) => {
+                write!(f, "unknown sensor mode {mode:#04b} in ctrl_meas")
+            }
+            BmeError::SelfTestFailed(e) => write!(f, "self test failed: {e}"),
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl<I2C> std::error::Error for BmeError<I2C>
+where
+    I2C: I2c<SevenBitAddress>,
+    I2C::Error: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BmeError::WriteError { source, .. } | BmeError::WriteReadError { source, .. } => {
+                Some(source)
+            }
+            BmeError::InvalidCalibration(e) => Some(e),
+            BmeError::SelfTestFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Returned when a setting could not be parsed from a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError;
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("unknown setting")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// Returned when a configuration contains values the sensor doesn't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The heater duration is longer than the maximum of 4032ms.
+    HeaterDurationTooLong,
+    /// A heater profile other than profile 0 is selected while the gas measurement is disabled.
+    GasProfileWithoutGasEnabled,
+    /// The gas measurement is enabled but the humidity isn't measured.
+    GasWithoutHumidity,
+}
+
+impl core::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConfigError::HeaterDurationTooLong => {
+                write!(
+                    f,
+                    "heater duration longer than {MAX_HEATER_WAIT_DURATION_MS}ms"
+                )
+            }
+            ConfigError::GasProfileWithoutGasEnabled => {
+                f.write_str("heater profile selected without enabling the gas measurement")
+            }
+            ConfigError::GasWithoutHumidity => {
+                f.write_str("gas measurement enabled without measuring the humidity")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
+/// Returned when the calibration data read from the sensor can't be right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// The temperature coefficient par_t1 is 0 or 0xFFFF.
+    TemperatureCoefficientInvalid,
+    /// The pressure coefficient par_p1 is 0 or 0xFFFF.
+    PressureCoefficientInvalid,
+    /// The humidity coefficient par_h2 is 0, which would make every humidity reading 0%.
+    HumidityCoefficientZero,
+}
+
+impl core::fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CalibrationError::TemperatureCoefficientInvalid => {
+                f.write_str("temperature coefficient par_t1 is 0 or 0xFFFF")
+            }
+            CalibrationError::PressureCoefficientInvalid => {
+                f.write_str("pressure coefficient par_p1 is 0 or 0xFFFF")
+            }
+            CalibrationError::HumidityCoefficientZero => {
+                f.write_str("humidity coefficient par_h2 is 0")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CalibrationError {}
+
+/// Returned by the self test when a measured value isn't plausible. Contains the measured value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelfTestError {
+    /// The temperature in °C is outside of the plausible range.
+    TemperatureOutOfRange(f32),
+    /// The pressure in Pa is outside of the plausible range.
+    PressureOutOfRange(f32),
+    /// The relative humidity in % is outside of the plausible range.
+    HumidityOutOfRange(f32),
+}
+
+impl core::fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SelfTestError::TemperatureOutOfRange(t) => {
+                write!(f, "temperature {t}°C outside of the plausible range")
+            }
+            SelfTestError::PressureOutOfRange(p) => {
+                write!(f, "pressure {p}Pa outside of the plausible range")
+            }
+            SelfTestError::HumidityOutOfRange(h) => {
+                write!(f, "humidity {h}% outside of the plausible range")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelfTestError {}
@@ -0,0 +1,139 @@
+use heapless::HistoryBuffer;
+
+use crate::MeasurementData;
+
+/// Minimum, maximum and mean of a measured value over the recorded window.
+/// The values have the unit of the measured value, e.g. Pa for the pressure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+impl Statistics {
+    fn from_values(values: impl Iterator<Item = f32>) -> Option<Self> {
+        let mut count = 0;
+        let mut sum = 0.;
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        for value in values {
+            count += 1;
+            sum += value;
+            min = min.min(value);
+            max = max.max(value);
+        }
+        if count == 0 {
+            None
+        } else {
+            Some(Self {
+                min,
+                max,
+                mean: sum / count as f32,
+            })
+        }
+    }
+}
+
+/// Keeps the last `N` measurements, e.g. for trend displays.
+/// Once full the oldest measurement is overwritten.
+pub struct MeasurementHistory<const N: usize> {
+    buffer: HistoryBuffer<MeasurementData, N>,
+}
+
+impl<const N: usize> MeasurementHistory<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: HistoryBuffer::new(),
+        }
+    }
+    /// Adds a measurement, replacing the oldest one if the history is full.
+    pub fn record(&mut self, data: MeasurementData) {
+        self.buffer.write(data);
+    }
+    /// Number of recorded measurements
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() == 0
+    }
+    /// The most recently recorded measurement
+    pub fn latest(&self) -> Option<&MeasurementData> {
+        self.buffer.recent()
+    }
+    /// Recorded measurements from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &MeasurementData> {
+        self.buffer.oldest_ordered()
+    }
+    /// Temperature statistics in °C. None if nothing was recorded.
+    pub fn temperature(&self) -> Option<Statistics> {
+        Statistics::from_values(self.buffer.iter().map(|data| data.temperature))
+    }
+    /// Humidity statistics in %. None if nothing was recorded.
+    pub fn humidity(&self) -> Option<Statistics> {
+        Statistics::from_values(self.buffer.iter().map(|data| data.humidity))
+    }
+    /// Pressure statistics in Pa. None if nothing was recorded.
+    pub fn pressure(&self) -> Option<Statistics> {
+        Statistics::from_values(self.buffer.iter().map(|data| data.pressure))
+    }
+    /// Gas resistance statistics in Ohms. Measurements without gas resistance are skipped.
+    /// None if no recorded measurement has a gas resistance.
+    pub fn gas_resistance(&self) -> Option<Statistics> {
+        Statistics::from_values(self.buffer.iter().filter_map(|data| data.gas_resistance))
+    }
+}
+
+impl<const N: usize> Default for MeasurementHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use super::{MeasurementHistory, Statistics};
+    use crate::MeasurementData;
+
+    fn data(temperature: f32, gas_resistance: Option<f32>) -> MeasurementData {
+        MeasurementData {
+            temperature,
+            humidity: 40.,
+            pressure: 1000.,
+            gas_resistance,
+            humidity_clamped: false,
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = MeasurementHistory::<3>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.temperature(), None);
+        history.record(data(10., None));
+        history.record(data(20., Some(100.)));
+        history.record(data(30., None));
+        history.record(data(40., Some(300.)));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.latest().unwrap().temperature, 40.);
+        assert_eq!(
+            history.temperature(),
+            Some(Statistics {
+                min: 20.,
+                max: 40.,
+                mean: 30.
+            })
+        );
+        assert_eq!(
+            history.gas_resistance(),
+            Some(Statistics {
+                min: 100.,
+                max: 300.,
+                mean: 200.
+            })
+        );
+        let temperatures = history.iter().map(|data| data.temperature);
+        assert!(temperatures.eq([20., 30., 40.]));
+    }
+}
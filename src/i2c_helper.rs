@@ -1,13 +1,14 @@
 use embedded_hal::delay::DelayNs;
 use embedded_hal::i2c::{I2c, SevenBitAddress};
-use log::debug;
 
-use crate::bitfields::{CtrlMeasurment, RawConfig, RawData};
-use crate::config::{Configuration, GasConfig, SensorMode, Variant};
+use crate::bitfields::{CtrlMeasurment, RawConfig};
+use crate::config::{Configuration, GasConfig, RetryPolicy, SensorMode, Variant};
 use crate::constants::{
     ADDRS_CONFIG, ADDR_CONFIG, ADDR_CONTROL_MODE, ADDR_GAS_WAIT_0, ADDR_RES_HEAT_0,
     ADDR_SENSOR_RESULT, ADDR_VARIANT_ID, DELAY_PERIOD_US, LEN_CONFIG,
 };
+#[cfg(feature = "trace")]
+use crate::trace::{RegisterOp, Tracer};
 use crate::{
     config::DeviceAddress,
     constants::{
@@ -16,6 +17,7 @@ use crate::{
     },
     data::CalibrationData,
     error::BmeError,
+    logging::debug,
 };
 
 // needed to convert h1 and h2 calibration parameters
@@ -32,6 +34,14 @@ pub struct I2CHelper<I2C, D> {
     address: u8,
     delayer: D,
     pub ambient_temperature: i32,
+    // last heater target written to res_heat_0
+    pub res_heat_0: Option<u8>,
+    // read back config registers after writing them
+    pub verify_writes: bool,
+    // retry failed transfers on the bus
+    pub retry_policy: RetryPolicy,
+    #[cfg(feature = "trace")]
+    pub tracer: Option<Tracer>,
 }
 impl<I2C, D> I2CHelper<I2C, D>
 where
@@ -44,14 +54,37 @@ where
         delayer: D,
         ambient_temperature: i32,
     ) -> Result<Self, BmeError<I2C>> {
+        Self::with_interface(i2c_interface, device_address, delayer, ambient_temperature).init()
+    }
+    /// Same as new but only checks the chip id without soft resetting the sensor
+    pub fn new_no_reset(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        ambient_temperature: i32,
+    ) -> Result<Self, BmeError<I2C>> {
+        Self::with_interface(i2c_interface, device_address, delayer, ambient_temperature)
+            .check_chip_id()
+    }
+    // doesn't communicate with the sensor
+    pub fn with_interface(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        ambient_temperature: i32,
+    ) -> Self {
         Self {
             i2c_interface,
             address: device_address.into(),
             delayer,
             // current ambient temperature. Needed to calculate the target temperature of the heater
             ambient_temperature,
+            res_heat_0: None,
+            verify_writes: false,
+            retry_policy: RetryPolicy::default(),
+            #[cfg(feature = "trace")]
+            tracer: None,
         }
-        .init()
     }
 
     pub fn into_inner(self) -> I2C {
@@ -61,12 +94,21 @@ where
     pub fn delay(&mut self, duration_us: u32) {
         self.delayer.delay_us(duration_us);
     }
-    fn get_register(&mut self, address: u8) -> Result<u8, BmeError<I2C>> {
+    pub fn get_register(&mut self, address: u8) -> Result<u8, BmeError<I2C>> {
         debug!("    Getting register: {address:x}.");
         let mut buffer = [0; 1];
-        self.i2c_interface
-            .write_read(self.address, &[address], &mut buffer)
-            .map_err(BmeError::WriteReadError)?;
+        self.with_retries(|i2c, device_address| {
+            i2c.write_read(device_address, &[address], &mut buffer)
+        })
+        .map_err(|source| BmeError::WriteReadError {
+            addr: address,
+            source,
+        })?;
+        #[cfg(feature = "trace")]
+        self.trace(RegisterOp::Read {
+            addr: address,
+            data: &buffer,
+        });
         Ok(buffer[0])
     }
     pub fn get_registers(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), BmeError<I2C>> {
@@ -75,16 +117,73 @@ where
             buffer.len() + address as usize,
             buffer.len()
         );
-        self.i2c_interface
-            .write_read(self.address, &[address], buffer)
-            .map_err(BmeError::WriteReadError)?;
+        self.with_retries(|i2c, device_address| i2c.write_read(device_address, &[address], buffer))
+            .map_err(|source| BmeError::WriteReadError {
+                addr: address,
+                source,
+            })?;
+        #[cfg(feature = "trace")]
+        self.trace(RegisterOp::Read {
+            addr: address,
+            data: buffer,
+        });
+        Ok(())
+    }
+    // Writes the register and reads it back if write verification is enabled
+    pub fn set_register(&mut self, address: u8, value: u8) -> Result<(), BmeError<I2C>> {
+        self.set_register_unverified(address, value)?;
+        if self.verify_writes {
+            let read = self.get_register(address)?;
+            if read != value {
+                return Err(BmeError::WriteVerificationFailed {
+                    addr: address,
+                    wrote: value,
+                    read,
+                });
+            }
+        }
         Ok(())
     }
-    fn set_register(&mut self, address: u8, value: u8) -> Result<(), BmeError<I2C>> {
+    // Used for registers that don't read back the written value, like the soft reset or the forced mode trigger
+    fn set_register_unverified(&mut self, address: u8, value: u8) -> Result<(), BmeError<I2C>> {
         debug!("    Setting register {address:x} to {value:b}");
-        self.i2c_interface
-            .write(self.address, &[address, value])
-            .map_err(BmeError::WriteError)
+        self.with_retries(|i2c, device_address| i2c.write(device_address, &[address, value]))
+            .map_err(|source| BmeError::WriteError {
+                addr: address,
+                source,
+            })?;
+        #[cfg(feature = "trace")]
+        self.trace(RegisterOp::Write {
+            addr: address,
+            value,
+        });
+        Ok(())
+    }
+    #[cfg(feature = "trace")]
+    fn trace(&self, op: RegisterOp<'_>) {
+        if let Some(tracer) = self.tracer {
+            tracer(op);
+        }
+    }
+    // Runs the transfer again according to the retry policy as long as the bus reports an error
+    fn with_retries<T>(
+        &mut self,
+        mut transfer: impl FnMut(&mut I2C, u8) -> Result<T, I2C::Error>,
+    ) -> Result<T, I2C::Error> {
+        let mut attempt = 0;
+        loop {
+            match transfer(&mut self.i2c_interface, self.address) {
+                Err(_) if attempt < self.retry_policy.retries => {
+                    attempt += 1;
+                    debug!(
+                        "    Transfer failed. Retrying ({attempt}/{})",
+                        self.retry_policy.retries
+                    );
+                    self.delayer.delay_us(self.retry_policy.delay_us);
+                }
+                result => return result,
+            }
+        }
     }
 
     // takes register pairs like [(addr, val), (addr, val)]
@@ -101,18 +200,22 @@ where
     fn init(mut self) -> Result<Self, BmeError<I2C>> {
         self.soft_reset()?;
         self.delayer.delay_ms(DELAY_PERIOD_US);
+        self.check_chip_id()
+    }
+    fn check_chip_id(mut self) -> Result<Self, BmeError<I2C>> {
         let chip_id = self.get_chip_id()?;
-        if chip_id != CHIP_ID {
-            Err(BmeError::UnexpectedChipId(chip_id))
-        } else {
-            Ok(self)
+        match chip_id {
+            CHIP_ID => Ok(self),
+            // floating bus or missing pull-ups
+            0x00 | 0xFF => Err(BmeError::SensorNotResponding(chip_id)),
+            _ => Err(BmeError::UnexpectedChipId(chip_id)),
         }
     }
     pub fn soft_reset(&mut self) -> Result<(), BmeError<I2C>> {
         debug!("Soft resetting");
-        self.set_register(ADDR_SOFT_RESET, CMD_SOFT_RESET)
+        self.set_register_unverified(ADDR_SOFT_RESET, CMD_SOFT_RESET)
     }
-    fn get_chip_id(&mut self) -> Result<u8, BmeError<I2C>> {
+    pub fn get_chip_id(&mut self) -> Result<u8, BmeError<I2C>> {
         debug!("Getting chip id");
         self.get_register(ADDR_CHIP_ID)
     }
@@ -121,6 +224,8 @@ where
         Ok(self.get_register(ADDR_VARIANT_ID)?.into())
     }
     // fills buffer with content from 3 seperate reads
+    // The blocks 0x8A..=0xA0, 0xE1..=0xEE and 0x00..=0x04 are not adjacent in the register map,
+    // so they can't be combined into fewer reads.
     pub fn get_calibration_data(&mut self) -> Result<CalibrationData, BmeError<I2C>> {
         debug!("Getting calibration data");
         let mut coeff_buffer = [0; LEN_COEFF_ALL];
@@ -154,12 +259,13 @@ where
             let current_mode = control_register.mode();
             debug!("Current mode: {current_mode:?}");
             // Put sensor to sleep unless it already in sleep mode. Same as in the reference implementation
+            // Undecodable modes (e.g. parallel mode of the BME688) are sent to sleep as well
             match current_mode {
-                SensorMode::Sleep => break control_register,
-                SensorMode::Forced => {
+                Ok(SensorMode::Sleep) => break control_register,
+                Ok(SensorMode::Forced) | Err(_) => {
                     control_register.set_mode(SensorMode::Sleep);
                     debug!("Setting control register to: {control_register:?}");
-                    self.set_register(ADDR_CONTROL_MODE, control_register.0)?;
+                    self.set_register_unverified(ADDR_CONTROL_MODE, control_register.0)?;
                     self.delayer.delay_ms(DELAY_PERIOD_US);
                 }
             }
@@ -171,16 +277,31 @@ where
                 // Change to forced mode. Last two bits=01.
                 control_register.set_mode(SensorMode::Forced);
                 debug!("Setting control register to: {control_register:?}");
-                self.set_register(ADDR_CONTROL_MODE, control_register.0)
+                self.set_register_unverified(ADDR_CONTROL_MODE, control_register.0)
             }
         }
     }
+    /// Reads the current mode from the ctrl_meas register
+    pub fn get_mode(&mut self) -> Result<SensorMode, BmeError<I2C>> {
+        CtrlMeasurment(self.get_register(ADDR_CONTROL_MODE)?)
+            .mode()
+            .map_err(BmeError::UnknownSensorMode)
+    }
     pub fn get_config(&mut self) -> Result<RawConfig<[u8; LEN_CONFIG]>, BmeError<I2C>> {
         debug!("Getting config");
         let mut buffer = [0; LEN_CONFIG];
         self.get_registers(ADDR_CONFIG, &mut buffer)?;
         Ok(RawConfig(buffer))
     }
+    /// Writes all config registers
+    pub fn set_raw_config(
+        &mut self,
+        raw_config: &RawConfig<[u8; LEN_CONFIG]>,
+    ) -> Result<(), BmeError<I2C>> {
+        let pairs = ADDRS_CONFIG.iter().zip(raw_config.0.iter());
+        debug!("Setting config registers");
+        self.set_registers_iter(pairs)
+    }
     /// Gets current config and applies all present values in given config
     /// Returns the new raw config
     pub fn set_config(
@@ -190,16 +311,13 @@ where
     ) -> Result<RawConfig<[u8; LEN_CONFIG]>, BmeError<I2C>> {
         let mut current_conf = self.get_config()?;
         current_conf.apply_config(conf);
-
-        let pairs = ADDRS_CONFIG.iter().zip(current_conf.0.iter());
-        debug!("Setting config registers");
-        self.set_registers_iter(pairs)?;
+        self.set_raw_config(&current_conf)?;
         if let Some(gas_conf) = &conf.gas_config {
             self.set_gas_config(gas_conf, calibration_data)?;
         }
         Ok(current_conf)
     }
-    fn set_gas_config(
+    pub fn set_gas_config(
         &mut self,
         gas_config: &GasConfig,
         calibration_data: &CalibrationData,
@@ -210,13 +328,12 @@ where
         debug!("Setting res_heat_0 to {res_heat}");
         self.set_register(ADDR_GAS_WAIT_0, gas_wait)?;
         self.set_register(ADDR_RES_HEAT_0, res_heat)?;
+        self.res_heat_0 = Some(res_heat);
         Ok(())
     }
     /// Get raw sensor data. 15 bytes starting at 0x1D
-    pub fn get_field_data(&mut self) -> Result<RawData<[u8; 15]>, BmeError<I2C>> {
-        let mut buffer: [u8; 15] = [0; 15];
-        self.get_registers(ADDR_SENSOR_RESULT, &mut buffer)?;
-        Ok(RawData(buffer))
+    pub fn get_field_data(&mut self, buffer: &mut [u8; 15]) -> Result<(), BmeError<I2C>> {
+        self.get_registers(ADDR_SENSOR_RESULT, buffer)
     }
 }
 pub fn extract_calibration_data(coeff_buffer: [u8; 42]) -> CalibrationData {
@@ -293,9 +410,11 @@ mod i2c_tests {
     extern crate std;
     use super::I2CHelper;
     use crate::{
-        config::DeviceAddress,
+        config::{DeviceAddress, RetryPolicy},
         constants::{ADDR_CHIP_ID, ADDR_SOFT_RESET, CHIP_ID, CMD_SOFT_RESET},
+        error::BmeError,
     };
+    use embedded_hal::i2c::ErrorKind;
     use embedded_hal_mock::eh1::{
         delay::NoopDelay,
         i2c::{Mock as I2cMock, Transaction as I2cTransaction},
@@ -305,19 +424,12 @@ mod i2c_tests {
     // primary device address
     const DEVICE_ADDRESS: u8 = 0x76;
     fn setup() -> Vec<I2cTransaction> {
-        let mut transactions = vec![];
-        // reset chip
-        transactions.push(I2cTransaction::write(
-            DEVICE_ADDRESS,
-            vec![ADDR_SOFT_RESET, CMD_SOFT_RESET],
-        ));
-        // get chip id
-        transactions.push(I2cTransaction::write_read(
-            DEVICE_ADDRESS,
-            vec![ADDR_CHIP_ID],
-            vec![CHIP_ID],
-        ));
-        transactions
+        vec![
+            // reset chip
+            I2cTransaction::write(DEVICE_ADDRESS, vec![ADDR_SOFT_RESET, CMD_SOFT_RESET]),
+            // get chip id
+            I2cTransaction::write_read(DEVICE_ADDRESS, vec![ADDR_CHIP_ID], vec![CHIP_ID]),
+        ]
     }
     // i2c mock tests
     #[test]
@@ -328,4 +440,40 @@ mod i2c_tests {
             I2CHelper::new(i2c_interface, DeviceAddress::Primary, NoopDelay {}, 20).unwrap();
         i2c_helper.into_inner().done();
     }
+    #[test]
+    fn test_retry_policy() {
+        let mut transactions = setup();
+        transactions.push(
+            I2cTransaction::write_read(DEVICE_ADDRESS, vec![ADDR_CHIP_ID], vec![0])
+                .with_error(ErrorKind::Other),
+        );
+        transactions.push(I2cTransaction::write_read(
+            DEVICE_ADDRESS,
+            vec![ADDR_CHIP_ID],
+            vec![CHIP_ID],
+        ));
+        // retries exhausted
+        for _ in 0..2 {
+            transactions.push(
+                I2cTransaction::write_read(DEVICE_ADDRESS, vec![ADDR_CHIP_ID], vec![0])
+                    .with_error(ErrorKind::Other),
+            );
+        }
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut i2c_helper =
+            I2CHelper::new(i2c_interface, DeviceAddress::Primary, NoopDelay {}, 20).unwrap();
+        i2c_helper.retry_policy = RetryPolicy {
+            retries: 1,
+            delay_us: 100,
+        };
+        assert_eq!(i2c_helper.get_register(ADDR_CHIP_ID).unwrap(), CHIP_ID);
+        assert!(matches!(
+            i2c_helper.get_register(ADDR_CHIP_ID),
+            Err(BmeError::WriteReadError {
+                addr: ADDR_CHIP_ID,
+                source: ErrorKind::Other
+            })
+        ));
+        i2c_helper.into_inner().done();
+    }
 }
@@ -0,0 +1,105 @@
+//! Rough air quality heuristics based on the gas resistance and humidity.
+//!
+//! None of these are calibrated measurements. They are simple, widely used approximations
+//! and are no replacement for Bosch's BSEC library or a dedicated CO2 sensor.
+
+// gas resistance in Ohms mapped to the worst and best gas score
+const GAS_LOWER_LIMIT: f32 = 5000.;
+const GAS_UPPER_LIMIT: f32 = 50000.;
+// relative humidity in % considered optimal
+const HUMIDITY_REFERENCE: f32 = 40.;
+// share of the humidity in the air quality score
+const HUMIDITY_WEIGHTING: f32 = 0.25;
+// eCO2 range in ppm the air quality score is mapped to
+const ECO2_MIN: f32 = 400.;
+const ECO2_MAX: f32 = 2000.;
+
+/// Estimates an "eCO2-like" value in ppm from the gas resistance in Ohms and the relative humidity in %.
+///
+/// **This is not a CO2 measurement.** The BME680 has no CO2 sensing element and only reacts to volatile organic compounds.
+/// The value is derived from an air quality score in the range 0 to 100:
+/// up to 25 points for the deviation of the humidity from 40% and up to 75 points for a gas resistance between 5kΩ and 50kΩ.
+/// The score is then mapped linearly onto 400ppm (best) to 2000ppm (worst).
+/// Only use it as a rough trend indicator, e.g. for hobby displays.
+pub fn estimate_eco2(gas_resistance: f32, humidity: f32) -> f32 {
+    let score = humidity_score(humidity) + gas_score(gas_resistance);
+    ECO2_MAX - (ECO2_MAX - ECO2_MIN) * score / 100.
+}
+
+/// Combines gas resistance and relative humidity into an air quality percentage from 0 (bad) to 100 (good).
+///
+/// This is the widely copied heuristic from the Pimoroni BME680 examples, **not** Bosch's BSEC IAQ.
+/// The humidity accounts for 25% and the gas resistance for 75% of the result:
+/// * The humidity part is 25 at `hum_baseline` (typically 40%) and falls linearly to 0 at 0% and 100%.
+/// * The gas part is 75 if the gas resistance is at or above `gas_baseline` and proportionally less below it.
+///   The gas baseline is usually the average gas resistance in Ohms after a burn-in period in clean air.
+pub fn gas_percentage(
+    gas_resistance: f32,
+    humidity: f32,
+    gas_baseline: f32,
+    hum_baseline: f32,
+) -> f32 {
+    let humidity_weight = HUMIDITY_WEIGHTING * 100.;
+    let hum_offset = humidity - hum_baseline;
+    let hum_score = if hum_offset > 0. {
+        (100. - hum_baseline - hum_offset) / (100. - hum_baseline) * humidity_weight
+    } else {
+        (hum_baseline + hum_offset) / hum_baseline * humidity_weight
+    };
+    let gas_score = if gas_resistance < gas_baseline {
+        gas_resistance / gas_baseline * (100. - humidity_weight)
+    } else {
+        100. - humidity_weight
+    };
+    hum_score + gas_score
+}
+
+// 0 to 25 points, most points at the reference humidity
+fn humidity_score(humidity: f32) -> f32 {
+    let humidity = humidity.clamp(0., 100.);
+    let max_score = HUMIDITY_WEIGHTING * 100.;
+    if humidity >= HUMIDITY_REFERENCE {
+        max_score * (100. - humidity) / (100. - HUMIDITY_REFERENCE)
+    } else {
+        max_score * humidity / HUMIDITY_REFERENCE
+    }
+}
+
+// 0 to 75 points, higher resistance means less volatile organic compounds
+fn gas_score(gas_resistance: f32) -> f32 {
+    let gas_resistance = gas_resistance.clamp(GAS_LOWER_LIMIT, GAS_UPPER_LIMIT);
+    let max_score = (1. - HUMIDITY_WEIGHTING) * 100.;
+    max_score * (gas_resistance - GAS_LOWER_LIMIT) / (GAS_UPPER_LIMIT - GAS_LOWER_LIMIT)
+}
+
+#[cfg(test)]
+mod iaq_tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::{estimate_eco2, gas_percentage};
+
+    #[test]
+    fn test_estimate_eco2() {
+        // clean air at optimal humidity
+        assert_abs_diff_eq!(estimate_eco2(50000., 40.), 400.);
+        assert_abs_diff_eq!(estimate_eco2(100000., 40.), 400.);
+        // polluted air and extreme humidity
+        assert_abs_diff_eq!(estimate_eco2(5000., 100.), 2000.);
+        assert_abs_diff_eq!(estimate_eco2(1000., 0.), 2000.);
+        // only the humidity is off
+        assert_abs_diff_eq!(estimate_eco2(50000., 70.), 600.);
+        let worse = estimate_eco2(20000., 40.);
+        let better = estimate_eco2(30000., 40.);
+        assert!(worse > better);
+    }
+    #[test]
+    fn test_gas_percentage() {
+        assert_abs_diff_eq!(gas_percentage(50000., 40., 50000., 40.), 100.);
+        // above the baseline doesn't count more
+        assert_abs_diff_eq!(gas_percentage(80000., 40., 50000., 40.), 100.);
+        assert_abs_diff_eq!(gas_percentage(25000., 40., 50000., 40.), 62.5);
+        assert_abs_diff_eq!(gas_percentage(50000., 70., 50000., 40.), 87.5);
+        assert_abs_diff_eq!(gas_percentage(50000., 20., 50000., 40.), 87.5);
+        assert_abs_diff_eq!(gas_percentage(0., 100., 50000., 40.), 0.);
+    }
+}
@@ -7,26 +7,101 @@
 //!
 //! For further information about the sensors capabilities and settings refer to the official [product page](https://www.bosch-sensortec.com/products/environmental-sensors/gas-sensors/bme680/).
 
-// TODO add example here
+//!
+//! # Example
+//! ```rust
+//! use bosch_bme680::{Bme680, Configuration, DeviceAddress, IIRFilter, Oversampling};
+//! # use bosch_bme680::{CalibrationData, GasConfig};
+//! # use embedded_hal_mock::eh1::{delay::NoopDelay, i2c::{Mock, Transaction}};
+//! # const CALIBRATION: [u8; 42] = [
+//! #     179, 193, 176, 188, 21, 51, 11, 29, 222, 179, 184, 1, 230, 47, 209, 22, 154, 34, 237, 70,
+//! #     148, 134, 44, 13, 204, 61, 206, 69, 18, 43, 124, 164, 92, 132, 19, 63, 29, 28, 201, 140,
+//! #     70, 24,
+//! # ];
+//! # let expected_config = Configuration::builder()
+//! #     .temperature_oversampling(Oversampling::By2)
+//! #     .pressure_oversampling(Oversampling::By16)
+//! #     .humidity_oversampling(Oversampling::By1)
+//! #     .filter(IIRFilter::Coeff3)
+//! #     .build();
+//! # let raw_config = expected_config.to_raw_registers([0; 5]);
+//! # let gas_config = GasConfig::default();
+//! # let res_heat = gas_config.calc_res_heat(&CalibrationData::from_raw_bytes(CALIBRATION), 20);
+//! # let mut transactions = vec![
+//! #     Transaction::write(0x76, vec![0xE0, 0xB6]),
+//! #     Transaction::write_read(0x76, vec![0xD0], vec![0x61]),
+//! #     Transaction::write_read(0x76, vec![0x8A], CALIBRATION[..23].to_vec()),
+//! #     Transaction::write_read(0x76, vec![0xE1], CALIBRATION[23..37].to_vec()),
+//! #     Transaction::write_read(0x76, vec![0x00], CALIBRATION[37..].to_vec()),
+//! #     Transaction::write_read(0x76, vec![0x71], vec![0; 5]),
+//! # ];
+//! # for (offset, value) in raw_config.into_iter().enumerate() {
+//! #     transactions.push(Transaction::write(0x76, vec![0x71 + offset as u8, value]));
+//! # }
+//! # transactions.extend([
+//! #     Transaction::write(0x76, vec![0x64, gas_config.calc_gas_wait()]),
+//! #     Transaction::write(0x76, vec![0x5A, res_heat]),
+//! #     Transaction::write_read(0x76, vec![0xF0], vec![0]),
+//! #     // forced mode and a finished measurement frame
+//! #     Transaction::write_read(0x76, vec![0x74], vec![raw_config[3]]),
+//! #     Transaction::write(0x76, vec![0x74, raw_config[3] | 0b01]),
+//! #     Transaction::write_read(
+//! #         0x76,
+//! #         vec![0x1D],
+//! #         vec![0x80, 0, 0xCA, 0, 0, 0x3E, 0xDB, 0, 0xCF, 0x39, 0, 0, 0, 0x81, 0xF3],
+//! #     ),
+//! # ]);
+//! # let i2c = Mock::new(&transactions);
+//! # let delay = NoopDelay::new();
+//! // `i2c` and `delay` are the embedded-hal implementations of your HAL
+//! let config = Configuration::builder()
+//!     .temperature_oversampling(Oversampling::By2)
+//!     .pressure_oversampling(Oversampling::By16)
+//!     .humidity_oversampling(Oversampling::By1)
+//!     .filter(IIRFilter::Coeff3)
+//!     .build();
+//! let mut bme = Bme680::new(i2c, DeviceAddress::Primary, delay, &config, 20).unwrap();
+//!
+//! let data = bme.measure().unwrap();
+//! assert!((0.0..=100.0).contains(&data.humidity));
+//! assert!(data.gas_resistance.is_some());
+//! # bme.into_inner().done();
+//! ```
 #![no_std]
 #![forbid(unsafe_code)]
 
-use self::config::{SensorMode, Variant};
-use bitfields::RawConfig;
+#[cfg(feature = "std")]
+extern crate std;
+
+use bitfields::{GasWaitDuration, RawConfig, RawData, RawMeasurementStatus};
+use config::HeaterProfile;
 use constants::{
-    CYCLE_DURATION, GAS_MEAS_DURATION, LEN_CONFIG, TPH_SWITCHING_DURATION, WAKEUP_DURATION,
-};
-use data::CalibrationData;
-use embedded_hal::{
-    delay::DelayNs,
+    ADDRS_CONFIG, ADDR_CHIP_ID, ADDR_CONFIG, ADDR_GAS_WAIT_0, ADDR_RES_HEAT_0, ADDR_SENSOR_RESULT,
+    ADDR_VARIANT_ID, CYCLE_DURATION, GAS_MEAS_DURATION, LEN_CONFIG, MEASUREMENT_TIMEOUT_FACTOR,
+    POLLS_PER_DELAY_PERIOD, TPH_SWITCHING_DURATION, WAKEUP_DURATION,
 };
-use embedded_hal::i2c::{I2c, SevenBitAddress};
+use data::calculate_temperature;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error as _, ErrorKind, I2c, SevenBitAddress};
 use i2c_helper::I2CHelper;
 
-pub use self::config::{Configuration, DeviceAddress, GasConfig, IIRFilter, Oversampling};
-use crate::data::{calculate_humidity, calculate_pressure, calculate_temperature};
-pub use data::MeasurmentData;
-pub use error::BmeError;
+pub use self::config::{
+    ConfigDiff, Configuration, DeviceAddress, GasConfig, IIRFilter, Oversampling, PlausibleRanges,
+    RetryPolicy, SensorMode, Variant,
+};
+#[cfg(feature = "debug-internals")]
+pub use data::CompensationDebug;
+pub use data::{
+    CMeasurement, CalibrationData, FieldData, MeasurementData, MeasurementStatus, MilliMeasurement,
+    Quality, SensorEvent,
+};
+#[allow(deprecated)]
+pub use data::{MeasurmentData, MeasurmentStatus};
+pub use error::{BmeError, CalibrationError, ConfigError, ParseError, SelfTestError};
+#[cfg(feature = "heapless")]
+pub use history::{MeasurementHistory, Statistics};
+#[cfg(feature = "trace")]
+pub use trace::{RegisterOp, Tracer};
 
 mod bitfields;
 mod calculations;
@@ -34,9 +109,23 @@ mod config;
 mod constants;
 mod data;
 mod error;
+#[cfg(feature = "heapless")]
+mod history;
 mod i2c_helper;
+#[cfg(feature = "iaq")]
+pub mod iaq;
+mod logging;
+#[cfg(feature = "trace")]
+mod trace;
 
 /// Sensor driver
+///
+/// The driver takes ownership of the i2c interface. Since embedded-hal implements [`I2c`] for mutable references,
+/// a borrowed interface (`&mut I2C`) can be passed instead, which can be used again once the driver is dropped.
+/// To share the bus with other devices while the driver is alive use [embedded-hal-bus](https://crates.io/crates/embedded-hal-bus).
+///
+/// Apart from the i2c interface and the delay the driver only holds plain data,
+/// so it is [`Send`] and [`Sync`] whenever `I2C` and `D` are. This allows moving it between tasks or threads.
 pub struct Bme680<I2C, D> {
     // actually communicates with sensor
     i2c: I2CHelper<I2C, D>,
@@ -44,8 +133,20 @@ pub struct Bme680<I2C, D> {
     calibration_data: CalibrationData,
     // used to calculate measurement delay period
     sensor_config: RawConfig<[u8; LEN_CONFIG]>,
+    // last configuration passed by the user
+    current_sensor_config: Configuration,
     // needed to calculate the gas resistance since it differs between bme680 and bme688
     variant: Variant,
+    // a measurement was triggered by measure_then_trigger and hasn't been read yet
+    measurement_pending: bool,
+    // measurements left to discard after applying the configuration
+    warmup_remaining: u8,
+    // the data registers are read at least this often before a measurement times out
+    min_polls: u8,
+    // number of measurements returned to the user
+    measurement_count: u32,
+    // calibration data and variant have been read and the configuration has been applied
+    initialized: bool,
 }
 impl<I2C, D> Bme680<I2C, D>
 where
@@ -66,84 +167,796 @@ where
         sensor_config: &Configuration,
         ambient_temperature: i32,
     ) -> Result<Self, BmeError<I2C>> {
-        let mut i2c = I2CHelper::new(i2c_interface, device_address, delayer, ambient_temperature)?;
-
-        let calibration_data = i2c.get_calibration_data()?;
-        let sensor_config = i2c.set_config(sensor_config, &calibration_data)?;
-        let variant = i2c.get_variant_id()?;
-        let bme = Self {
-            i2c,
-            calibration_data,
-            sensor_config,
-            variant,
-        };
-
+        let i2c = I2CHelper::new(i2c_interface, device_address, delayer, ambient_temperature)?;
+        Self::from_helper(i2c, sensor_config.clone())
+    }
+    /// Same as [`Bme680::new`] but takes the configuration by value, moving it into the driver instead of cloning it.
+    /// Convenient when the configuration is built just for this call, e.g. with [`Configuration::builder`].
+    pub fn new_owned(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        sensor_config: Configuration,
+        ambient_temperature: i32,
+    ) -> Result<Self, BmeError<I2C>> {
+        let i2c = I2CHelper::new(i2c_interface, device_address, delayer, ambient_temperature)?;
+        Self::from_helper(i2c, sensor_config)
+    }
+    /// Same as [`Bme680::new`] but doesn't soft reset the sensor, e.g. if it has already been configured
+    /// by a bootloader or another driver instance. The chip id is still checked.
+    ///
+    /// The sensor is put to sleep and the given configuration is applied on top of the register values already set,
+    /// so settings that are None in the configuration are inherited from the previous owner instead of being reset
+    /// to the sensor's defaults.
+    pub fn new_no_reset(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        sensor_config: &Configuration,
+        ambient_temperature: i32,
+    ) -> Result<Self, BmeError<I2C>> {
+        let mut i2c =
+            I2CHelper::new_no_reset(i2c_interface, device_address, delayer, ambient_temperature)?;
+        i2c.set_mode(SensorMode::Sleep)?;
+        Self::from_helper(i2c, sensor_config.clone())
+    }
+    /// Same as [`Bme680::new`] but returns `Ok(None)` if no BME680 is present at the address,
+    /// i.e. the device doesn't acknowledge the soft reset or chip id read, or reports an unexpected chip id.
+    /// Errors after the sensor has been identified are still returned.
+    ///
+    /// Pass a mutable reference to the i2c interface to try the other address if no sensor was found:
+    /// ```rust
+    /// # use bosch_bme680::{Bme680, Configuration, DeviceAddress};
+    /// # use embedded_hal_mock::eh1::{delay::NoopDelay, i2c::{Mock, Transaction}};
+    /// # use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+    /// # let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+    /// # let mut i2c = Mock::new(&[
+    /// #     Transaction::write(0x76, vec![0xE0, 0xB6]).with_error(nack),
+    /// #     Transaction::write(0x77, vec![0xE0, 0xB6]).with_error(nack),
+    /// # ]);
+    /// let config = Configuration::default();
+    /// let mut bme = None;
+    /// for address in [DeviceAddress::Primary, DeviceAddress::Secondary] {
+    ///     bme = Bme680::probe(&mut i2c, address, NoopDelay::new(), &config, 20).unwrap();
+    ///     if bme.is_some() {
+    ///         break;
+    ///     }
+    /// }
+    /// assert!(bme.is_none());
+    /// # i2c.done();
+    /// ```
+    pub fn probe(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        sensor_config: &Configuration,
+        ambient_temperature: i32,
+    ) -> Result<Option<Self>, BmeError<I2C>> {
+        match I2CHelper::new(i2c_interface, device_address, delayer, ambient_temperature) {
+            Ok(i2c) => Self::from_helper(i2c, sensor_config.clone()).map(Some),
+            Err(BmeError::UnexpectedChipId(_) | BmeError::SensorNotResponding(_)) => Ok(None),
+            Err(BmeError::WriteError { source, .. } | BmeError::WriteReadError { source, .. })
+                if matches!(source.kind(), ErrorKind::NoAcknowledge(_)) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    /// Same as [`Bme680::new`] but doesn't communicate with the sensor until the first measurement, e.g. for nodes
+    /// that power the sensor up only for a single measurement and should go back to sleep as fast as possible.
+    ///
+    /// The sensor isn't reset and the chip id isn't checked. The calibration data is read and the configuration is
+    /// applied on the first measurement or configuration change and cached afterwards.
+    /// The tradeoff is that a missing or wrong sensor is only detected when the first measurement fails,
+    /// and [`Bme680::get_calibration_data`] and [`Bme680::variant`] return placeholders until then.
+    /// [`Bme680::preview_heater_registers`] and [`Bme680::res_heat_for`] calculate with the placeholder
+    /// calibration data until then, so their results are meaningless before the first measurement.
+    pub fn new_lazy(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+        sensor_config: &Configuration,
+        ambient_temperature: i32,
+    ) -> Self {
+        let i2c =
+            I2CHelper::with_interface(i2c_interface, device_address, delayer, ambient_temperature);
+        Self::uninitialized(i2c, sensor_config.clone())
+    }
+    fn from_helper(
+        i2c: I2CHelper<I2C, D>,
+        sensor_config: Configuration,
+    ) -> Result<Self, BmeError<I2C>> {
+        let mut bme = Self::uninitialized(i2c, sensor_config);
+        bme.ensure_initialized()?;
         Ok(bme)
     }
+    fn uninitialized(i2c: I2CHelper<I2C, D>, sensor_config: Configuration) -> Self {
+        Self {
+            i2c,
+            calibration_data: CalibrationData::from_raw_bytes([0; 42]),
+            sensor_config: RawConfig([0; LEN_CONFIG]),
+            warmup_remaining: sensor_config.warmup_discard,
+            current_sensor_config: sensor_config,
+            variant: Variant::GasLow,
+            measurement_pending: false,
+            min_polls: 0,
+            measurement_count: 0,
+            initialized: false,
+        }
+    }
+    // Reads the calibration data, applies the configuration and reads the variant unless that was done before.
+    fn ensure_initialized(&mut self) -> Result<(), BmeError<I2C>> {
+        if self.initialized {
+            return Ok(());
+        }
+        let calibration_data = self.i2c.get_calibration_data()?;
+        calibration_data
+            .validate()
+            .map_err(BmeError::InvalidCalibration)?;
+        self.sensor_config = self
+            .i2c
+            .set_config(&self.current_sensor_config, &calibration_data)?;
+        self.calibration_data = calibration_data;
+        self.variant = self.i2c.get_variant_id()?;
+        self.initialized = true;
+        Ok(())
+    }
+    /// Creates a new instance of the Sensor with [`Configuration::default`] and an assumed ambient temperature of 20°C.
+    ///
+    /// The ambient temperature is only used to calculate the heater target temperature.
+    /// Every measurement updates it with the measured temperature, so the heater target is corrected the next time
+    /// a configuration is applied with [`Bme680::set_configuration`], even if it's the same configuration.
+    /// Use [`Bme680::new`] to pass a different configuration or ambient temperature.
+    pub fn new_with_defaults(
+        i2c_interface: I2C,
+        device_address: DeviceAddress,
+        delayer: D,
+    ) -> Result<Self, BmeError<I2C>> {
+        Self::new(
+            i2c_interface,
+            device_address,
+            delayer,
+            &Configuration::default(),
+            20,
+        )
+    }
     /// Returns the wrapped i2c interface
     pub fn into_inner(self) -> I2C {
         self.i2c.into_inner()
     }
+    /// Puts the sensor to sleep and returns the wrapped i2c interface.
+    /// # Errors
+    /// If the sensor couldn't be put to sleep the error is returned together with the i2c interface.
+    pub fn release(mut self) -> Result<I2C, (BmeError<I2C>, I2C)> {
+        match self.put_to_sleep() {
+            Ok(()) => Ok(self.into_inner()),
+            Err(e) => Err((e, self.into_inner())),
+        }
+    }
 
+    /// Enables reading back every config register after writing it, to catch writes corrupted on noisy buses.
+    /// This doubles the number of transactions when configuring the sensor and is disabled by default.
+    /// A mismatch is returned as [`BmeError::WriteVerificationFailed`].
+    pub fn set_write_verification(&mut self, enabled: bool) {
+        self.i2c.verify_writes = enabled;
+    }
+    /// Sets how often the data registers are read at least before a measurement times out.
+    ///
+    /// The timeout of a measurement is counted in the time requested from the delay provider, not in wall clock time.
+    /// With the default of 0 the data is read about 9 times (once after the expected duration, then 4 times per
+    /// expected duration until 3 times the expected duration have been requested). If the delay provider returns early
+    /// or does nothing at all, these reads happen faster than the conversion and the measurement times out although
+    /// the sensor works. Raising the minimum keeps polling until the sensor is done, with every read bounded by the
+    /// bus speed instead of the delay.
+    pub fn set_min_polls(&mut self, min_polls: u8) {
+        self.min_polls = min_polls;
+    }
+    /// Retries register reads and writes that failed on the i2c bus, e.g. because of occasional NACKs on long cables.
+    /// Only bus errors ([`BmeError::WriteError`] and [`BmeError::WriteReadError`]) are retried,
+    /// errors caused by the sensor state are returned immediately.
+    /// The policy applies to all transfers after the driver has been created.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.i2c.retry_policy = retry_policy;
+        self
+    }
+    /// Reads the current power mode of the sensor from the ctrl_meas register (0x74).
+    /// The sensor is back in [`SensorMode::Sleep`] once a measurement has finished.
+    /// # Errors
+    /// [`BmeError::UnknownSensorMode`] if the mode bits are neither sleep nor forced mode.
+    pub fn current_mode(&mut self) -> Result<SensorMode, BmeError<I2C>> {
+        self.i2c.get_mode()
+    }
+    /// Reports every register read and write to the given callback, e.g. to capture a transcript of the communication.
+    /// Pass None to remove the callback.
+    #[cfg(feature = "trace")]
+    pub fn set_trace(&mut self, tracer: Option<Tracer>) {
+        self.i2c.tracer = tracer;
+    }
     fn put_to_sleep(&mut self) -> Result<(), BmeError<I2C>> {
+        self.measurement_pending = false;
         self.i2c.set_mode(SensorMode::Sleep)
     }
+    /// Applies the configuration to the sensor.
+    ///
+    /// If the configuration equals the current one only the heater registers are written, and only if
+    /// the heater target changed with the ambient temperature updated by the measurements since it was written.
+    /// Nothing is written otherwise.
     pub fn set_configuration(&mut self, config: &Configuration) -> Result<(), BmeError<I2C>> {
+        self.ensure_initialized()?;
+        if *config == self.current_sensor_config {
+            if let Some(gas_config) = &config.gas_config {
+                let res_heat = self.res_heat_for(gas_config, self.i2c.ambient_temperature);
+                if self.i2c.res_heat_0 != Some(res_heat) {
+                    self.put_to_sleep()?;
+                    self.i2c
+                        .set_gas_config(gas_config, &self.calibration_data)?;
+                }
+            }
+            return Ok(());
+        }
         self.put_to_sleep()?;
         let new_config = self.i2c.set_config(config, &self.calibration_data)?;
         // current conf is used to calculate measurement delay period
         self.sensor_config = new_config;
+        self.current_sensor_config = config.clone();
+        self.warmup_remaining = config.warmup_discard;
+        Ok(())
+    }
+    /// Changes the oversampling of temperature, pressure and humidity, e.g. to switch between high precision and low power.
+    /// Only the registers ctrl_hum (0x72) and ctrl_meas (0x74) are written, the filter and gas settings are left untouched.
+    pub fn set_oversampling(
+        &mut self,
+        temperature: Oversampling,
+        pressure: Oversampling,
+        humidity: Oversampling,
+    ) -> Result<(), BmeError<I2C>> {
+        self.ensure_initialized()?;
+        self.put_to_sleep()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        raw_config.set_temperature_oversampling(temperature);
+        raw_config.set_pressure_oversampling(pressure);
+        raw_config.set_humidity_oversampling(humidity);
+        raw_config.set_mode(SensorMode::Sleep.into());
+        // changes to ctrl_hum only become effective after writing ctrl_meas
+        self.i2c.set_register(ADDRS_CONFIG[1], raw_config.0[1])?;
+        self.i2c.set_register(ADDRS_CONFIG[3], raw_config.0[3])?;
+        self.sensor_config = raw_config;
+        self.current_sensor_config.temperature_oversampling = Some(temperature);
+        self.current_sensor_config.pressure_oversampling = Some(pressure);
+        self.current_sensor_config.humidity_oversampling = Some(humidity);
+        Ok(())
+    }
+    /// Changes only the gas measurement, e.g. to ramp the heater temperature during a gas scan.
+    /// The heater registers of profile 0 and the run_gas bit are written, oversampling and filter are left untouched.
+    /// Passing None disables the gas measurement by clearing run_gas.
+    ///
+    /// Like [`Bme680::set_configuration`] the next [`Configuration::warmup_discard`] measurements are discarded.
+    pub fn set_gas_config(&mut self, gas_config: Option<GasConfig>) -> Result<(), BmeError<I2C>> {
+        self.ensure_initialized()?;
+        self.put_to_sleep()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        if let Some(gas_config) = &gas_config {
+            self.i2c
+                .set_gas_config(gas_config, &self.calibration_data)?;
+        }
+        raw_config.set_run_gas(gas_config.is_some());
+        raw_config.set_heater_profile(HeaterProfile::Profile0);
+        self.i2c.set_register(ADDRS_CONFIG[0], raw_config.0[0])?;
+        self.sensor_config = raw_config;
+        self.current_sensor_config.gas_config = gas_config;
+        self.warmup_remaining = self.current_sensor_config.warmup_discard;
+        Ok(())
+    }
+    /// Puts the sensor into its lowest-current state, e.g. before shipping battery powered devices.
+    /// All measurements are skipped, the IIR filter and the gas heater are turned off and the sensor is put to sleep.
+    /// In this state the sensor draws its sleep current of typically 0.15µA.
+    /// Unlike putting the sensor to sleep the previous configuration is not kept.
+    /// Call [`Bme680::set_configuration`] to start measuring again.
+    pub fn prepare_for_storage(&mut self) -> Result<(), BmeError<I2C>> {
+        self.ensure_initialized()?;
+        self.put_to_sleep()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        raw_config.set_temperature_oversampling(Oversampling::Skipped);
+        raw_config.set_pressure_oversampling(Oversampling::Skipped);
+        raw_config.set_humidity_oversampling(Oversampling::Skipped);
+        raw_config.set_filter(IIRFilter::Coeff0);
+        raw_config.set_run_gas(false);
+        raw_config.set_mode(SensorMode::Sleep.into());
+        self.i2c.set_raw_config(&raw_config)?;
+        self.sensor_config = raw_config;
+        self.current_sensor_config = Configuration {
+            temperature_oversampling: Some(Oversampling::Skipped),
+            pressure_oversampling: Some(Oversampling::Skipped),
+            humidity_oversampling: Some(Oversampling::Skipped),
+            filter: Some(IIRFilter::Coeff0),
+            gas_config: None,
+            ..Configuration::default()
+        };
+        Ok(())
+    }
+    /// Trigger a new measurement, see the [crate level example](crate#example).
+    /// The first [`Configuration::warmup_discard`] measurements after applying a configuration are taken and discarded first.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure(&mut self) -> Result<MeasurementData, BmeError<I2C>> {
+        self.measure_with_status().map(|(data, _status)| data)
+    }
+    /// Trigger a new measurement and additionally return the status flags of the measurement.
+    /// The status is decoded from the same registers as the measurement data, so this doesn't cost any extra I²C transactions.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_with_status(
+        &mut self,
+    ) -> Result<(MeasurementData, MeasurementStatus), BmeError<I2C>> {
+        let mut buffer = [0; 15];
+        self.measure_into(&mut buffer, None)
+    }
+    /// Same as [`Bme680::measure`] but waits for the measurement using the given delay function
+    /// instead of the delay passed on construction. The function receives the delay in microseconds.
+    /// This allows e.g. yielding to a scheduler while the sensor is measuring.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_with<F: FnMut(u32)>(
+        &mut self,
+        mut delay: F,
+    ) -> Result<MeasurementData, BmeError<I2C>> {
+        let mut buffer = [0; 15];
+        self.measure_into(&mut buffer, Some(&mut delay))
+            .map(|(data, _status)| data)
+    }
+    /// Same as [`Bme680::measure`] but reads the raw sensor data into the given buffer,
+    /// so it can be reused between measurements.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_with_buffer(
+        &mut self,
+        buffer: &mut [u8; 15],
+    ) -> Result<MeasurementData, BmeError<I2C>> {
+        self.measure_into(buffer, None).map(|(data, _status)| data)
+    }
+    fn measure_into(
+        &mut self,
+        buffer: &mut [u8; 15],
+        custom_delay: Option<&mut (dyn FnMut(u32) + '_)>,
+    ) -> Result<(MeasurementData, MeasurementStatus), BmeError<I2C>> {
+        let measurement = self.sample_into(buffer, custom_delay)?;
+        self.count_measurement();
+        Ok(measurement)
+    }
+    // Same as measure_into but doesn't count the measurement, e.g. for samples combined into one returned measurement.
+    fn sample_into(
+        &mut self,
+        buffer: &mut [u8; 15],
+        mut custom_delay: Option<&mut (dyn FnMut(u32) + '_)>,
+    ) -> Result<(MeasurementData, MeasurementStatus), BmeError<I2C>> {
+        self.ensure_initialized()?;
+        let delay_period = self.calculate_delay_period_us();
+        self.discard_warmup(delay_period, buffer, custom_delay.as_deref_mut())?;
+        self.trigger_and_read(
+            delay_period,
+            Self::is_measurement_finished,
+            buffer,
+            custom_delay,
+        )?;
+        let raw_data = RawData(buffer);
+        let data = self.compensate(&raw_data);
+        Ok((data, MeasurementStatus::from(&raw_data)))
+    }
+    // Takes and discards the remaining warm-up measurements of the current configuration.
+    // Only compensated to keep the ambient temperature for the heater target up to date.
+    fn discard_warmup(
+        &mut self,
+        delay_period: u32,
+        buffer: &mut [u8; 15],
+        mut custom_delay: Option<&mut (dyn FnMut(u32) + '_)>,
+    ) -> Result<(), BmeError<I2C>> {
+        while self.warmup_remaining > 0 {
+            self.trigger_and_read(
+                delay_period,
+                Self::is_measurement_finished,
+                buffer,
+                custom_delay.as_deref_mut(),
+            )?;
+            self.compensate(&RawData(&buffer));
+            self.warmup_remaining -= 1;
+        }
         Ok(())
     }
-    /// Trigger a new measurement.
+    /// Same as [`Bme680::measure`] but additionally returns t_fine, e.g. for a custom compensation of pressure and humidity.
+    ///
+    /// t_fine is the fine temperature value the Bosch compensation formulas use to calculate pressure and humidity.
+    /// It corresponds to the temperature in °C multiplied by 5120 and doesn't include the
+    /// [`Configuration::temperature_offset_centi`], so it is the value the pressure was compensated with.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_with_tfine(&mut self) -> Result<(MeasurementData, f32), BmeError<I2C>> {
+        let mut buffer = [0; 15];
+        let (data, _status) = self.measure_into(&mut buffer, None)?;
+        let temperature_adc = RawData(&buffer).temperature_adc().0;
+        let (_, t_fine) = calculate_temperature(temperature_adc, &self.calibration_data);
+        Ok((data, t_fine))
+    }
+    /// Same as [`Bme680::measure`] but additionally returns the raw adc values and the intermediate values of the
+    /// compensation, e.g. to diagnose implausible humidity readings in the field.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    #[cfg(feature = "debug-internals")]
+    pub fn measure_debug(&mut self) -> Result<CompensationDebug, BmeError<I2C>> {
+        let mut buffer = [0; 15];
+        let (data, _status) = self.measure_into(&mut buffer, None)?;
+        Ok(CompensationDebug::new(
+            &RawData(&buffer),
+            &self.calibration_data,
+            self.current_sensor_config.temperature_offset(),
+            data,
+        ))
+    }
+    /// Same as [`Bme680::measure`] but doesn't update the ambient temperature used to calculate the heater target,
+    /// e.g. to keep the heater setting identical across an experiment when the configuration is applied again
+    /// (applying the same configuration only rewrites the heater target if the ambient temperature changed it).
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_fixed_ambient(&mut self) -> Result<MeasurementData, BmeError<I2C>> {
+        let ambient_temperature = self.i2c.ambient_temperature;
+        let result = self.measure();
+        self.i2c.ambient_temperature = ambient_temperature;
+        result
+    }
+    /// Reads a measurement and immediately triggers the next one, so the next conversion runs
+    /// while the data is processed by the caller. This shortens the sampling period when measuring in a loop.
+    ///
+    /// The BME680 has no continuous mode, it returns to sleep after every forced measurement.
+    /// The first call triggers a measurement and waits for it like [`Bme680::measure`],
+    /// following calls only wait for the measurement triggered by the previous call.
+    /// The measurement triggered by the last call is never read, the sensor returns to sleep once it has finished.
     /// # Errors
-    /// If no new data is generated in 5 tries a Timeout error is returned.
-    // Sets the sensor mode to forced
-    // Tries to wait 5 times for new data with a delay calculated based on the set sensor config
-    // If no new data could be read in those 5 attempts a Timeout error is returned
-    pub fn measure(&mut self) -> Result<MeasurmentData, BmeError<I2C>> {
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_then_trigger(&mut self) -> Result<MeasurementData, BmeError<I2C>> {
+        self.ensure_initialized()?;
+        let delay_period = self.calculate_delay_period_us();
+        let mut buffer = [0; 15];
+        let mut pending_read = false;
+        if self.measurement_pending {
+            self.measurement_pending = false;
+            // the measurement has been running since the previous call, so poll right away
+            self.wait_for_data(
+                delay_period,
+                0,
+                Self::is_measurement_finished,
+                &mut buffer,
+                None,
+            )?;
+            pending_read = true;
+            if self.warmup_remaining > 0 {
+                // the pending measurement is one of the warm-up measurements
+                self.compensate(&RawData(&buffer));
+                self.warmup_remaining -= 1;
+                pending_read = false;
+            }
+        }
+        if !pending_read {
+            self.discard_warmup(delay_period, &mut buffer, None)?;
+            self.trigger_and_read(
+                delay_period,
+                Self::is_measurement_finished,
+                &mut buffer,
+                None,
+            )?;
+        }
         self.i2c.set_mode(SensorMode::Forced)?;
+        self.measurement_pending = true;
+        Ok(self.process_raw_data(&RawData(&buffer)))
+    }
+    /// Checks the progress of a measurement without blocking, e.g. to drive a state machine.
+    ///
+    /// If no measurement is running, a new one is triggered and [`SensorEvent::Busy`] is returned.
+    /// Otherwise the data registers are read once and the state of the measurement is returned.
+    /// After [`SensorEvent::DataReady`] or [`SensorEvent::Timeout`] the next call triggers a new measurement.
+    /// Like [`Bme680::measure`] the first [`Configuration::warmup_discard`] measurements are discarded,
+    /// for these a new measurement is triggered and [`SensorEvent::Busy`] is returned instead of the data.
+    pub fn poll_event(&mut self) -> Result<SensorEvent, BmeError<I2C>> {
+        self.ensure_initialized()?;
+        if !self.measurement_pending {
+            self.i2c.set_mode(SensorMode::Forced)?;
+            self.measurement_pending = true;
+            return Ok(SensorEvent::Busy);
+        }
+        let mut buffer = [0; 15];
+        self.i2c.get_field_data(&mut buffer)?;
+        let raw_data = RawData(&mut buffer);
+        if Self::is_measurement_finished(&raw_data) {
+            self.measurement_pending = false;
+            if self.warmup_remaining > 0 {
+                self.compensate(&raw_data);
+                self.warmup_remaining -= 1;
+                return self.poll_event();
+            }
+            return Ok(SensorEvent::DataReady(self.process_raw_data(&raw_data)));
+        }
+        if raw_data.new_data() || raw_data.gas_measuring() {
+            Ok(SensorEvent::GasNotReady)
+        } else if raw_data.measuring() {
+            Ok(SensorEvent::Busy)
+        } else {
+            self.measurement_pending = false;
+            Ok(SensorEvent::Timeout)
+        }
+    }
+    /// Triggers `N` measurements back to back and returns them in the order they were taken.
+    /// The delay period is only calculated once for all measurements.
+    /// # Errors
+    /// Fails on the first measurement that returns an error, the remaining measurements aren't triggered.
+    pub fn measure_n<const N: usize>(&mut self) -> Result<[MeasurementData; N], BmeError<I2C>> {
+        self.ensure_initialized()?;
         let delay_period = self.calculate_delay_period_us();
-        self.i2c.delay(delay_period);
-        // try read new values 5 times and delay if no new data is available or the sensor is still measuring
-        for _i in 0..5 {
-            let raw_data = self.i2c.get_field_data()?;
-            if !raw_data.measuring() && raw_data.new_data() {
-                let (temperature, t_fine) =
-                    calculate_temperature(raw_data.temperature_adc().0, &self.calibration_data);
-                // update the current ambient temperature which is needed to calculate the target heater temp
-                self.i2c.ambient_temperature = temperature as i32;
-                let pressure =
-                    calculate_pressure(raw_data.pressure_adc().0, &self.calibration_data, t_fine);
-                let humidity =
-                    calculate_humidity(raw_data.humidity_adc().0, &self.calibration_data, t_fine);
-                let gas_resistance = if raw_data.gas_valid() && !raw_data.gas_measuring() {
-                    let gas_resistance = self.variant.calc_gas_resistance(
-                        raw_data.gas_adc().0,
-                        self.calibration_data.range_sw_err,
-                        raw_data.gas_range() as usize,
-                    );
-                    Some(gas_resistance)
-                } else {
-                    None
-                };
-
-                let data = MeasurmentData {
-                    temperature,
-                    gas_resistance,
-                    humidity,
-                    pressure,
-                };
-                return Ok(data);
-            } else {
-                self.i2c.delay(delay_period);
+        let mut buffer = [0; 15];
+        self.discard_warmup(delay_period, &mut buffer, None)?;
+        let mut measurements: [MeasurementData; N] = core::array::from_fn(|_| Default::default());
+        for measurement in &mut measurements {
+            self.trigger_and_read(
+                delay_period,
+                Self::is_measurement_finished,
+                &mut buffer,
+                None,
+            )?;
+            *measurement = self.process_raw_data(&RawData(&buffer));
+        }
+        Ok(measurements)
+    }
+    /// Takes `samples` measurements and averages the gas resistance of the measurements in the most common gas range.
+    ///
+    /// The sensor switches the gas range automatically, so consecutive gas resistances can jump when the range changes.
+    /// Measurements in other ranges are discarded, which smooths the gas resistance for simple air quality heuristics.
+    /// If several ranges are equally common, the lowest one is used. Temperature, pressure and humidity are those of
+    /// the last measurement. The tradeoff is latency: this takes `samples` times as long as [`Bme680::measure`].
+    /// At least one measurement is taken.
+    /// # Errors
+    /// Fails on the first measurement that returns an error.
+    pub fn measure_gas_stable(&mut self, samples: u8) -> Result<MeasurementData, BmeError<I2C>> {
+        // gas_range is a 4 bit field
+        let mut counts = [0u8; 16];
+        let mut sums = [0f32; 16];
+        let mut add_sample = |data: &MeasurementData, status: MeasurementStatus| {
+            if let Some(gas_resistance) = data.gas_resistance {
+                counts[status.gas_range as usize] += 1;
+                sums[status.gas_range as usize] += gas_resistance;
+            }
+        };
+        let mut buffer = [0; 15];
+        let (mut data, status) = self.sample_into(&mut buffer, None)?;
+        add_sample(&data, status);
+        for _ in 1..samples.max(1) {
+            let (sample, status) = self.sample_into(&mut buffer, None)?;
+            add_sample(&sample, status);
+            data = sample;
+        }
+        // the samples are returned as a single measurement
+        self.count_measurement();
+        let mut mode = 0;
+        for (range, count) in counts.iter().enumerate() {
+            if *count > counts[mode] {
+                mode = range;
+            }
+        }
+        data.gas_resistance = (counts[mode] > 0).then(|| sums[mode] / counts[mode] as f32);
+        Ok(data)
+    }
+    /// Runs `cycles` measurements and discards the results.
+    /// The first gas readings after enabling the heater are unreliable until the heater control loop has settled,
+    /// so call this after construction or after changing the gas config. A handful of cycles (e.g. 5) is usually enough.
+    /// # Errors
+    /// Fails on the first measurement that returns an error.
+    pub fn warm_up_gas(&mut self, cycles: u8) -> Result<(), BmeError<I2C>> {
+        for _ in 0..cycles {
+            self.measure()?;
+        }
+        Ok(())
+    }
+    /// Measures only the humidity with the configured humidity oversampling (at least x1).
+    /// The temperature is measured with oversampling x1 since it's needed to calculate the humidity.
+    /// Pressure and gas measurement are skipped to keep the conversion time short.
+    /// The previous configuration is restored afterwards.
+    /// Since the heater is off, the [`Configuration::warmup_discard`] measurements are neither taken nor used up.
+    pub fn measure_humidity(&mut self) -> Result<f32, BmeError<I2C>> {
+        self.ensure_initialized()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        raw_config.set_temperature_oversampling(Oversampling::By1);
+        raw_config.set_pressure_oversampling(Oversampling::Skipped);
+        if raw_config.humidity_oversampling() == Oversampling::Skipped {
+            raw_config.set_humidity_oversampling(Oversampling::By1);
+        }
+        raw_config.set_run_gas(false);
+        let data = self.measure_with_raw_config(raw_config)?;
+        Ok(data.humidity)
+    }
+    /// Measures only the pressure with the configured pressure oversampling (at least x1), e.g. for altimeters.
+    /// The temperature is measured with oversampling x1 since it's needed to calculate the pressure.
+    /// Humidity and gas measurement are skipped to keep the conversion time short.
+    /// The previous configuration is restored afterwards.
+    /// Since the heater is off, the [`Configuration::warmup_discard`] measurements are neither taken nor used up.
+    pub fn measure_pressure(&mut self) -> Result<f32, BmeError<I2C>> {
+        self.ensure_initialized()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        raw_config.set_temperature_oversampling(Oversampling::By1);
+        if raw_config.pressure_oversampling() == Oversampling::Skipped {
+            raw_config.set_pressure_oversampling(Oversampling::By1);
+        }
+        raw_config.set_humidity_oversampling(Oversampling::Skipped);
+        raw_config.set_run_gas(false);
+        let data = self.measure_with_raw_config(raw_config)?;
+        Ok(data.pressure)
+    }
+    /// Takes a measurement with the configured IIR filter followed by one with the filter disabled (`Coeff0`)
+    /// and returns both as `(filtered, unfiltered)`, e.g. to see the smoothing effect when choosing a filter coefficient.
+    /// The filter only affects temperature and pressure, humidity and gas are measured unfiltered either way.
+    ///
+    /// This costs two full measurement cycles instead of one, plus rewriting the config registers
+    /// before and after the unfiltered measurement. The previous configuration is restored afterwards.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_filtered_and_raw(
+        &mut self,
+    ) -> Result<(MeasurementData, MeasurementData), BmeError<I2C>> {
+        let filtered = self.measure()?;
+        let mut raw_config = RawConfig(self.sensor_config.0);
+        raw_config.set_filter(IIRFilter::Coeff0);
+        let unfiltered = self.measure_with_raw_config(raw_config)?;
+        Ok((filtered, unfiltered))
+    }
+    // Runs a single measurement with the given config registers and restores the previous registers afterwards.
+    // The warm-up measurements aren't taken, they are meant for the heater settings of the configuration.
+    fn measure_with_raw_config(
+        &mut self,
+        mut raw_config: RawConfig<[u8; LEN_CONFIG]>,
+    ) -> Result<MeasurementData, BmeError<I2C>> {
+        let mut previous_config = RawConfig(self.sensor_config.0);
+        previous_config.set_mode(SensorMode::Sleep.into());
+        raw_config.set_mode(SensorMode::Sleep.into());
+        self.put_to_sleep()?;
+        // the delay period is calculated from the cached registers
+        self.sensor_config = RawConfig(raw_config.0);
+        let mut buffer = [0; 15];
+        let result = self.i2c.set_raw_config(&raw_config).and_then(|()| {
+            self.trigger_and_read(
+                self.calculate_delay_period_us(),
+                Self::is_measurement_finished,
+                &mut buffer,
+                None,
+            )
+        });
+        // restore the previous config even if the measurement failed. The cache is restored first,
+        // so later read-modify-writes build on the previous config even if writing it back fails.
+        self.sensor_config = RawConfig(previous_config.0);
+        self.put_to_sleep()?;
+        self.i2c.set_raw_config(&previous_config)?;
+        result.map(|()| self.process_raw_data(&RawData(&buffer)))
+    }
+    /// Trigger a new measurement and return as soon as temperature, pressure and humidity are available,
+    /// without waiting for the gas measurement to finish.
+    /// The gas resistance is None if the gas measurement wasn't finished yet.
+    /// Use [`Bme680::measure`] if the gas resistance is needed.
+    /// # Errors
+    /// If no new data is generated within 3 times the expected measurement duration a Timeout error is returned.
+    pub fn measure_no_gas_wait(&mut self) -> Result<MeasurementData, BmeError<I2C>> {
+        self.ensure_initialized()?;
+        let mut buffer = [0; 15];
+        // the warm-up needs the full measurement including the heater
+        self.discard_warmup(self.calculate_delay_period_us(), &mut buffer, None)?;
+        let delay_period = self.calculate_tph_duration_us();
+        self.trigger_and_read(
+            delay_period,
+            |raw_data| raw_data.new_data(),
+            &mut buffer,
+            None,
+        )?;
+        Ok(self.process_raw_data(&RawData(buffer)))
+    }
+    // compensates a measurement that is returned to the user and counts it
+    fn process_raw_data<T: AsRef<[u8]>>(&mut self, raw_data: &RawData<T>) -> MeasurementData {
+        let data = self.compensate(raw_data);
+        self.count_measurement();
+        data
+    }
+    fn count_measurement(&mut self) {
+        self.measurement_count = self.measurement_count.wrapping_add(1);
+    }
+    fn compensate<T: AsRef<[u8]>>(&mut self, raw_data: &RawData<T>) -> MeasurementData {
+        let data = MeasurementData::from_raw(
+            raw_data,
+            &self.calibration_data,
+            &self.variant,
+            self.current_sensor_config.temperature_offset(),
+        );
+        // update the current ambient temperature which is needed to calculate the target heater temp
+        self.i2c.ambient_temperature = data.temperature as i32;
+        data
+    }
+    // Sets the sensor mode to forced and waits for the data
+    // On success the buffer contains the raw sensor data
+    fn trigger_and_read(
+        &mut self,
+        delay_period: u32,
+        is_ready: impl Fn(&RawData<&mut [u8; 15]>) -> bool,
+        buffer: &mut [u8; 15],
+        custom_delay: Option<&mut (dyn FnMut(u32) + '_)>,
+    ) -> Result<(), BmeError<I2C>> {
+        self.measurement_pending = false;
+        self.i2c.set_mode(SensorMode::Forced)?;
+        // pad the first wait for sensors that take a bit longer than the nominal conversion time
+        let delay_margin =
+            delay_period * self.current_sensor_config.delay_margin_percent as u32 / 100;
+        self.wait_for_data(
+            delay_period,
+            delay_period + delay_margin,
+            is_ready,
+            buffer,
+            custom_delay,
+        )
+    }
+    // Waits for the initial delay and polls for new data until a multiple of the delay period calculated based on
+    // the set sensor config has passed.
+    // If no new data could be read until then a Timeout error is returned
+    // A given custom delay is used instead of the stored delay implementation
+    fn wait_for_data(
+        &mut self,
+        delay_period: u32,
+        initial_delay: u32,
+        is_ready: impl Fn(&RawData<&mut [u8; 15]>) -> bool,
+        buffer: &mut [u8; 15],
+        mut custom_delay: Option<&mut (dyn FnMut(u32) + '_)>,
+    ) -> Result<(), BmeError<I2C>> {
+        let mut delay = |i2c: &mut I2CHelper<I2C, D>, us: u32| match custom_delay.as_deref_mut() {
+            Some(custom_delay) => custom_delay(us),
+            None => i2c.delay(us),
+        };
+        let timeout = delay_period * MEASUREMENT_TIMEOUT_FACTOR;
+        let poll_interval = delay_period.div_ceil(POLLS_PER_DELAY_PERIOD);
+        let mut elapsed = initial_delay;
+        let mut attempts: u8 = 0;
+        delay(&mut self.i2c, elapsed);
+        // read new values and delay if no new data is available or the sensor is still measuring
+        loop {
+            self.i2c.get_field_data(buffer)?;
+            attempts = attempts.saturating_add(1);
+            if is_ready(&RawData(buffer)) {
+                return Ok(());
+            }
+            if elapsed >= timeout && attempts >= self.min_polls {
+                // a sensor back in sleep mode without new data never started measuring
+                if !RawData(&buffer).new_data() {
+                    match self.i2c.get_mode() {
+                        Ok(SensorMode::Sleep) => return Err(BmeError::ForcedModeNotEntered),
+                        // an undecodable mode is reported as timeout below
+                        Ok(SensorMode::Forced) | Err(BmeError::UnknownSensorMode(_)) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                // Shouldn't happen
+                return Err(BmeError::MeasuringTimeOut { attempts });
             }
+            delay(&mut self.i2c, poll_interval);
+            elapsed += poll_interval;
         }
-        // Shouldn't happen
-        Err(BmeError::MeasuringTimeOut)
+    }
+    // finished measurement including the gas measurement
+    fn is_measurement_finished(raw_data: &RawData<&mut [u8; 15]>) -> bool {
+        !raw_data.measuring() && raw_data.new_data()
     }
     // calculates the delay period needed for a measurement in microseconds.
+    // Same as bme68x_get_meas_dur of the reference implementation plus the heater duration if the gas measurement is
+    // enabled, since the heater is brought up to temperature before the gas resistance is measured.
     fn calculate_delay_period_us(&self) -> u32 {
+        let mut delay_period = self.calculate_tph_duration_us() + GAS_MEAS_DURATION;
+        if self.sensor_config.run_gas() {
+            if let Some(gas_config) = &self.current_sensor_config.gas_config {
+                delay_period += gas_config.effective_heater_duration().as_micros() as u32;
+            }
+        }
+        delay_period
+    }
+    // calculates the duration of the temperature, pressure and humidity measurement in microseconds.
+    fn calculate_tph_duration_us(&self) -> u32 {
         let mut measurement_cycles: u32 = 0;
         measurement_cycles += self.sensor_config.temperature_oversampling().cycles();
         measurement_cycles += self.sensor_config.humidity_oversampling().cycles();
@@ -151,28 +964,152 @@ where
 
         let mut measurement_duration = measurement_cycles * CYCLE_DURATION;
         measurement_duration += TPH_SWITCHING_DURATION;
-        measurement_duration += GAS_MEAS_DURATION;
 
         measurement_duration += WAKEUP_DURATION;
 
         measurement_duration
     }
 
+    /// Reads a single register.
+    ///
+    /// Advanced: Meant for registers that aren't covered by the driver yet.
+    #[cfg(feature = "raw-register-access")]
+    pub fn read_register(&mut self, addr: u8) -> Result<u8, BmeError<I2C>> {
+        self.i2c.get_register(addr)
+    }
+    /// Writes a single register.
+    ///
+    /// Advanced: The driver doesn't know about registers changed this way, so writing config or mode
+    /// registers can leave it out of sync with the sensor. Prefer the high level methods where possible.
+    #[cfg(feature = "raw-register-access")]
+    pub fn write_register(&mut self, addr: u8, value: u8) -> Result<(), BmeError<I2C>> {
+        self.i2c.set_register(addr, value)
+    }
+    /// Reads the config registers 0x71 to 0x75 from the sensor, in this order: ctrl_gas_1 (0x71), ctrl_hum (0x72),
+    /// status (0x73, holds spi_mem_page), ctrl_meas (0x74) and config (0x75). ctrl_gas_0 (0x70) isn't read.
+    /// Useful to compare the applied configuration with the register values expected from the datasheet.
+    pub fn raw_config_bytes(&mut self) -> Result<[u8; LEN_CONFIG], BmeError<I2C>> {
+        Ok(self.i2c.get_config()?.0)
+    }
+    /// Reads the heater duration of profile 0 back from the gas_wait register (0x64).
+    /// The duration has the resolution of the register, see [`GasConfig::effective_heater_duration`].
+    pub fn read_gas_wait(&mut self) -> Result<core::time::Duration, BmeError<I2C>> {
+        let gas_wait = self.i2c.get_register(ADDR_GAS_WAIT_0)?;
+        Ok(GasWaitDuration::from(gas_wait).into())
+    }
+    /// Reads the heater resistance register of profile 0 (res_heat_0, 0x5A).
+    ///
+    /// The BME680 doesn't expose the heater resistance that was actually reached. The returned value is the target
+    /// written by [`GasConfig::calc_res_heat`], so comparing it with the expected value confirms the heater setting arrived.
+    /// Whether the heater reached the target during a measurement is reported by [`MeasurementStatus::heater_stable`].
+    pub fn read_heater_resistance(&mut self) -> Result<u8, BmeError<I2C>> {
+        self.i2c.get_register(ADDR_RES_HEAT_0)
+    }
+    /// Takes a measurement and checks that it's within the operating range of the datasheet,
+    /// see [`PlausibleRanges::default`]. Returns the measurement if it's plausible.
+    /// # Errors
+    /// [`BmeError::SelfTestFailed`] if a value is outside of its range, otherwise the errors of [`Bme680::measure`].
+    pub fn self_test(&mut self) -> Result<MeasurementData, BmeError<I2C>> {
+        self.self_test_with_bounds(PlausibleRanges::default())
+    }
+    /// Same as [`Bme680::self_test`] but with custom ranges, e.g. for applications in extreme environments.
+    /// # Errors
+    /// [`BmeError::SelfTestFailed`] if a value is outside of its range, otherwise the errors of [`Bme680::measure`].
+    pub fn self_test_with_bounds(
+        &mut self,
+        bounds: PlausibleRanges,
+    ) -> Result<MeasurementData, BmeError<I2C>> {
+        let data = self.measure()?;
+        bounds.check(&data).map_err(BmeError::SelfTestFailed)?;
+        Ok(data)
+    }
+    /// Reads the registers identifying the sensor, intended for diagnostics and hardware inventories.
+    ///
+    /// The chip id and variant id registers aren't adjacent, so the block is assembled from two reads:
+    /// | bytes | registers   | content                                      |
+    /// |-------|-------------|----------------------------------------------|
+    /// | 0     | 0xD0        | chip_id, 0x61 for BME680 and BME688          |
+    /// | 1..4  | 0xD1..=0xD3 | reserved                                     |
+    /// | 4     | 0xF0        | variant_id, 0x00 for BME680, 0x01 for BME688 |
+    /// | 5..8  | 0xF1..=0xF3 | reserved                                     |
+    ///
+    /// The reserved bytes are returned as read, they aren't documented by Bosch but may differ between silicon revisions.
+    pub fn read_identity_block(&mut self) -> Result<[u8; 8], BmeError<I2C>> {
+        let mut block = [0; 8];
+        let (chip, variant) = block.split_at_mut(4);
+        self.i2c.get_registers(ADDR_CHIP_ID, chip)?;
+        self.i2c.get_registers(ADDR_VARIANT_ID, variant)?;
+        Ok(block)
+    }
+    /// Reads the index of the heater profile the current data belongs to from register 0x1D (meas_status_0),
+    /// without reading the whole data block. Useful to follow the progress of a scan over several heater profiles.
+    pub fn gas_meas_index(&mut self) -> Result<u8, BmeError<I2C>> {
+        let status = RawMeasurementStatus(self.i2c.get_register(ADDR_SENSOR_RESULT)?);
+        Ok(status.gas_meas_index())
+    }
+    /// Reads the run_gas bit of register 0x71 from the sensor.
+    /// Useful to check that the gas measurement is actually enabled on the chip, e.g. after a failed config write,
+    /// and not only in the [`Configuration`].
+    pub fn is_gas_running(&mut self) -> Result<bool, BmeError<I2C>> {
+        let ctrl_gas_1 = self.i2c.get_register(ADDR_CONFIG)?;
+        Ok(RawConfig([ctrl_gas_1]).run_gas())
+    }
+    /// Reads the data registers without triggering a measurement and returns the uncompensated values.
+    /// Use it after a measurement has finished, e.g. to apply a custom compensation with [`Bme680::get_calibration_data`].
+    pub fn read_field(&mut self) -> Result<FieldData, BmeError<I2C>> {
+        let mut buffer = [0; 15];
+        self.i2c.get_field_data(&mut buffer)?;
+        Ok(FieldData::from(&RawData(buffer)))
+    }
+    /// Reads the chip id and the variant from the sensor.
+    /// A BME680 reports chip id 0x61 and [`Variant::GasLow`], a BME688 chip id 0x61 and [`Variant::GasHigh`].
+    pub fn identify(&mut self) -> Result<(u8, Variant), BmeError<I2C>> {
+        let chip_id = self.i2c.get_chip_id()?;
+        let variant = self.i2c.get_variant_id()?;
+        Ok((chip_id, variant))
+    }
+    /// Number of successful measurements returned by the driver since it was created, e.g. to detect missed samples.
+    /// Only measurements that returned data are counted, failed or timed out measurements and measurements discarded
+    /// during the warm up aren't. The counter wraps around at `u32::MAX`.
+    pub fn measurement_count(&self) -> u32 {
+        self.measurement_count
+    }
+    /// Returns the variant read from the sensor during initialization.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
     pub fn get_calibration_data(&self) -> &CalibrationData {
         &self.calibration_data
     }
+    /// Calculates the heater register values `(res_heat_0, gas_wait_0)` the given gas config would be written as,
+    /// using the calibration data and ambient temperature of the driver. Nothing is written to the sensor.
+    /// Meaningless before the first measurement of a driver created with [`Bme680::new_lazy`].
+    pub fn preview_heater_registers(&self, gas_config: &GasConfig) -> (u8, u8) {
+        let res_heat = self.res_heat_for(gas_config, self.i2c.ambient_temperature);
+        let gas_wait = gas_config.calc_gas_wait();
+        (res_heat, gas_wait)
+    }
+    /// Calculates the res_heat register value of the gas config for an arbitrary ambient temperature in °C
+    /// using the calibration data of the driver, e.g. to model the heater setting across temperatures.
+    /// Neither the sensor nor the ambient temperature stored in the driver are touched.
+    /// Meaningless before the first measurement of a driver created with [`Bme680::new_lazy`].
+    pub fn res_heat_for(&self, gas_config: &GasConfig, ambient_temperature: i32) -> u8 {
+        gas_config.calc_res_heat(&self.calibration_data, ambient_temperature)
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)] // grouped by register fields
 mod library_tests {
     extern crate std;
 
+    use std::string::ToString;
     use std::vec;
     use std::vec::Vec;
 
     use crate::constants::{
-        ADDR_CHIP_ID, ADDR_CONFIG, ADDR_CONTROL_MODE, ADDR_GAS_WAIT_0, ADDR_REG_COEFF1,
-        ADDR_REG_COEFF2, ADDR_REG_COEFF3, ADDR_RES_HEAT_0, ADDR_SOFT_RESET, ADDR_VARIANT_ID,
+        ADDR_CHIP_ID, ADDR_CONTROL_MODE, ADDR_GAS_WAIT_0, ADDR_REG_COEFF1, ADDR_REG_COEFF2,
+        ADDR_REG_COEFF3, ADDR_RES_HEAT_0, ADDR_SENSOR_RESULT, ADDR_SOFT_RESET, ADDR_VARIANT_ID,
         CHIP_ID, CMD_SOFT_RESET, LEN_COEFF1, LEN_COEFF2, LEN_COEFF3,
     };
     use crate::i2c_helper::extract_calibration_data;
@@ -272,6 +1209,72 @@ mod library_tests {
         ));
         transactions
     }
+    // ctrl_meas register content after applying the default config
+    const CTRL_MEAS_SLEEP: u8 = 0b010_101_00;
+    // frame with finished temperature, pressure, humidity and gas measurement
+    const FIELD_DATA: [u8; 15] = [
+        // new_data, gas_measuring, measuring, _, gas_meas_index
+        0b1_0_0_0_0000,
+        0,
+        // pressure
+        0xCA,
+        0x00,
+        0x00,
+        // temperature
+        0x3E,
+        0xDB,
+        0x00,
+        // humidity
+        0xCF,
+        0x39,
+        0,
+        0,
+        0,
+        // gas_r_msb gas_adc<9:2>
+        0b10000001,
+        // gas_r_lsb gas_adc<1:0>, gas_valid, heater_stable, gas_range
+        0b11_1_1_0011,
+    ];
+    fn add_measurement_transactions(transactions: &mut Vec<I2cTransaction>, field_data: [u8; 15]) {
+        add_measurement_transactions_with_ctrl_meas(transactions, field_data, CTRL_MEAS_SLEEP);
+    }
+    fn add_measurement_transactions_with_ctrl_meas(
+        transactions: &mut Vec<I2cTransaction>,
+        field_data: [u8; 15],
+        ctrl_meas: u8,
+    ) {
+        // set mode to forced
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE],
+            vec![ctrl_meas],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE, ctrl_meas | 0b01],
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_SENSOR_RESULT],
+            field_data.to_vec(),
+        ));
+    }
+    // default config registers after setup
+    const RAW_CONFIG: [u8; 5] = [
+        0b000_1_0000,
+        0b0_0_000_001,
+        0,
+        CTRL_MEAS_SLEEP,
+        0b000_001_00,
+    ];
+    fn add_raw_config_transactions(transactions: &mut Vec<I2cTransaction>, raw_config: [u8; 5]) {
+        for (address, value) in ADDRS_CONFIG.into_iter().zip(raw_config) {
+            transactions.push(I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![address, value],
+            ));
+        }
+    }
     fn add_sleep_to_sleep_transactions(transactions: &mut Vec<I2cTransaction>) {
         transactions.push(I2cTransaction::write_read(
             DeviceAddress::Primary.into(),
@@ -343,4 +1346,1487 @@ mod library_tests {
         bme.put_to_sleep().unwrap();
         bme.into_inner().done();
     }
+    #[test]
+    fn test_measure_with_status() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let (data, status) = bme.measure_with_status().unwrap();
+        assert!(data.gas_resistance.is_some());
+        assert_eq!(
+            status,
+            MeasurementStatus {
+                new_data: true,
+                measuring: false,
+                gas_measuring: false,
+                gas_meas_index: 0,
+                gas_valid: true,
+                heater_stable: true,
+                gas_range: 3,
+            }
+        );
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_prepare_for_storage() {
+        let mut transactions = setup_transactions();
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, [0; 5]);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.prepare_for_storage().unwrap();
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_preview_heater_registers() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let gas_config = GasConfig::default();
+        let expected_res_heat =
+            gas_config.calc_res_heat(&extract_calibration_data(CALIBRATION_DATA), 20);
+        let expected_gas_wait = gas_config.calc_gas_wait();
+        assert_eq!(
+            bme.preview_heater_registers(&gas_config),
+            (expected_res_heat, expected_gas_wait)
+        );
+        let cold_res_heat =
+            gas_config.calc_res_heat(&extract_calibration_data(CALIBRATION_DATA), -10);
+        assert_eq!(bme.res_heat_for(&gas_config, -10), cold_res_heat);
+        assert_eq!(bme.i2c.ambient_temperature, 20);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_borrowed_i2c() {
+        let mut transactions = setup_transactions();
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let mut i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            &mut i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.put_to_sleep().unwrap();
+        bme.measure().unwrap();
+        i2c_interface.done();
+    }
+    #[test]
+    fn test_warm_up_gas() {
+        let mut transactions = setup_transactions();
+        for _ in 0..3 {
+            add_measurement_transactions(&mut transactions, FIELD_DATA);
+        }
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.warm_up_gas(3).unwrap();
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_n() {
+        let mut transactions = setup_transactions();
+        for _ in 0..3 {
+            add_measurement_transactions(&mut transactions, FIELD_DATA);
+        }
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let measurements = bme.measure_n::<3>().unwrap();
+        for measurement in measurements {
+            assert!((39.0..=41.0).contains(&measurement.humidity));
+        }
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_n_timeout() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        // no new data, so the second measurement times out and the third one isn't triggered
+        let mut no_data = FIELD_DATA;
+        no_data[0] = 0;
+        add_measurement_transactions(&mut transactions, no_data);
+        // polled until 3 times the delay period has passed
+        for _ in 0..8 {
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                no_data.to_vec(),
+            ));
+        }
+        // still in forced mode, so the conversion took too long
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE],
+            vec![CTRL_MEAS_SLEEP | 0b01],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(
+            bme.measure_n::<3>(),
+            Err(BmeError::MeasuringTimeOut { attempts: 9 })
+        ));
+        assert_eq!(bme.measurement_count(), 1);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_unexpected_chip_id() {
+        let transactions = [
+            I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SOFT_RESET, CMD_SOFT_RESET],
+            ),
+            I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CHIP_ID],
+                vec![0x60],
+            ),
+        ];
+        let mut i2c_interface = I2cMock::new(&transactions);
+        let result = Bme680::new(
+            i2c_interface.clone(),
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        );
+        let Err(error) = result else {
+            panic!("Expected chip id error");
+        };
+        assert_eq!(error.to_string(), "unexpected chip id 0x60 (expected 0x61)");
+        i2c_interface.done();
+    }
+    #[test]
+    fn test_new_no_reset() {
+        // same as the setup but without the soft reset and with a sleep check before the configuration
+        let mut transactions = setup_transactions()[1..].to_vec();
+        transactions.insert(
+            1,
+            I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CONTROL_MODE],
+                vec![CTRL_MEAS_SLEEP],
+            ),
+        );
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new_no_reset(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_probe() {
+        let mut transactions = vec![
+            // nothing at the primary address
+            I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SOFT_RESET, CMD_SOFT_RESET],
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::NoAcknowledge(
+                embedded_hal::i2c::NoAcknowledgeSource::Address,
+            )),
+            // other chip
+            I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SOFT_RESET, CMD_SOFT_RESET],
+            ),
+            I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CHIP_ID],
+                vec![0x60],
+            ),
+        ];
+        transactions.extend(setup_transactions());
+        let mut i2c_interface = I2cMock::new(&transactions);
+        for _ in 0..2 {
+            let bme = Bme680::probe(
+                &mut i2c_interface,
+                DeviceAddress::Primary,
+                NoopDelay::new(),
+                &Configuration::default(),
+                20,
+            );
+            assert!(bme.unwrap().is_none());
+        }
+        let bme = Bme680::probe(
+            &mut i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        );
+        assert!(bme.unwrap().is_some());
+        i2c_interface.done();
+    }
+    #[test]
+    fn test_invalid_calibration() {
+        let mut transactions = setup_transactions();
+        // keep soft reset, chip id and the first calibration block
+        transactions.truncate(3);
+        // par_h2 is stored in 0xE1 and the upper nibble of 0xE2
+        let mut calibration_data_2 = CALIBRATION_DATA[LEN_COEFF1..LEN_COEFF1 + LEN_COEFF2].to_vec();
+        calibration_data_2[0] = 0;
+        calibration_data_2[1] &= 0x0F;
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_REG_COEFF2],
+            calibration_data_2,
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_REG_COEFF3],
+            CALIBRATION_DATA[LEN_COEFF1 + LEN_COEFF2..].to_vec(),
+        ));
+        let mut i2c_interface = I2cMock::new(&transactions);
+        let result = Bme680::new(
+            &mut i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        );
+        assert!(matches!(
+            result,
+            Err(BmeError::InvalidCalibration(
+                CalibrationError::HumidityCoefficientZero
+            ))
+        ));
+        i2c_interface.done();
+    }
+    #[test]
+    fn test_sensor_not_responding() {
+        for chip_id in [0x00, 0xFF] {
+            let transactions = [
+                I2cTransaction::write(
+                    DeviceAddress::Primary.into(),
+                    vec![ADDR_SOFT_RESET, CMD_SOFT_RESET],
+                ),
+                I2cTransaction::write_read(
+                    DeviceAddress::Primary.into(),
+                    vec![ADDR_CHIP_ID],
+                    vec![chip_id],
+                ),
+            ];
+            let mut i2c_interface = I2cMock::new(&transactions);
+            let result = Bme680::new(
+                i2c_interface.clone(),
+                DeviceAddress::Primary,
+                NoopDelay::new(),
+                &Configuration::default(),
+                20,
+            );
+            assert!(matches!(result, Err(BmeError::SensorNotResponding(id)) if id == chip_id));
+            i2c_interface.done();
+        }
+    }
+    #[test]
+    fn test_measure_humidity() {
+        let mut transactions = setup_transactions();
+        // temperature x1, pressure skipped, humidity x1, gas off
+        let humidity_config = [0, 0b0_0_000_001, 0, 0b001_000_00, 0b000_001_00];
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, humidity_config);
+        add_measurement_transactions_with_ctrl_meas(
+            &mut transactions,
+            FIELD_DATA,
+            humidity_config[3],
+        );
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, RAW_CONFIG);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let humidity = bme.measure_humidity().unwrap();
+        assert!((0.0..=100.0).contains(&humidity));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_humidity_restore_fails() {
+        let mut transactions = setup_transactions();
+        let humidity_config = [0, 0b0_0_000_001, 0, 0b001_000_00, 0b000_001_00];
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, humidity_config);
+        add_measurement_transactions_with_ctrl_meas(
+            &mut transactions,
+            FIELD_DATA,
+            humidity_config[3],
+        );
+        add_sleep_to_sleep_transactions(&mut transactions);
+        // writing back the previous config fails
+        transactions.push(
+            I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![ADDRS_CONFIG[0], RAW_CONFIG[0]],
+            )
+            .with_error(ErrorKind::Other),
+        );
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(
+            bme.measure_humidity(),
+            Err(BmeError::WriteError { .. })
+        ));
+        // the driver doesn't keep the temporary registers
+        assert_eq!(bme.sensor_config.0, RAW_CONFIG);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_filtered_and_raw() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        // same config with filter coeff 0
+        let mut unfiltered_config = RAW_CONFIG;
+        unfiltered_config[4] = 0;
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, unfiltered_config);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, RAW_CONFIG);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let (filtered, unfiltered) = bme.measure_filtered_and_raw().unwrap();
+        assert_eq!(filtered.temperature, unfiltered.temperature);
+        assert_eq!(bme.sensor_config.0, RAW_CONFIG);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_pressure() {
+        let mut transactions = setup_transactions();
+        // temperature x1, pressure x16, humidity skipped, gas off
+        let pressure_config = [0, 0, 0, 0b001_101_00, 0b000_001_00];
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, pressure_config);
+        add_measurement_transactions_with_ctrl_meas(
+            &mut transactions,
+            FIELD_DATA,
+            pressure_config[3],
+        );
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, RAW_CONFIG);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let pressure = bme.measure_pressure().unwrap();
+        assert!(pressure > 0.0);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_new_owned() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new_owned(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            Configuration::builder().build(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(bme.current_sensor_config, Configuration::default());
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_new_with_defaults() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme =
+            Bme680::new_with_defaults(i2c_interface, DeviceAddress::Primary, NoopDelay::new())
+                .unwrap();
+        assert_eq!(bme.current_sensor_config, Configuration::default());
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_raw_config_bytes() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG],
+            RAW_CONFIG.to_vec(),
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(bme.raw_config_bytes().unwrap(), RAW_CONFIG);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_read_gas_wait() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_GAS_WAIT_0],
+            vec![GasConfig::default().calc_gas_wait()],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(
+            bme.read_gas_wait().unwrap(),
+            GasConfig::default().effective_heater_duration()
+        );
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_read_heater_resistance() {
+        let mut transactions = setup_transactions();
+        let res_heat_0 =
+            GasConfig::default().calc_res_heat(&extract_calibration_data(CALIBRATION_DATA), 20);
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_RES_HEAT_0],
+            vec![res_heat_0],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(bme.read_heater_resistance().unwrap(), res_heat_0);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_read_identity_block() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CHIP_ID],
+            vec![CHIP_ID, 0x01, 0x00, 0x00],
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_VARIANT_ID],
+            vec![0x01, 0x00, 0x00, 0x40],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(
+            bme.read_identity_block().unwrap(),
+            [CHIP_ID, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x40]
+        );
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_self_test() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let data = bme.self_test().unwrap();
+        // a freezer application doesn't expect room temperature
+        let freezer = PlausibleRanges {
+            temperature: -40.0..=-10.0,
+            ..PlausibleRanges::default()
+        };
+        assert!(matches!(
+            bme.self_test_with_bounds(freezer),
+            Err(BmeError::SelfTestFailed(SelfTestError::TemperatureOutOfRange(t))) if t == data.temperature
+        ));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_fixed_ambient() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let data = bme.measure_fixed_ambient().unwrap();
+        assert_ne!(data.temperature as i32, 20);
+        assert_eq!(bme.i2c.ambient_temperature, 20);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_min_polls() {
+        let mut transactions = setup_transactions();
+        let mut no_data = FIELD_DATA;
+        no_data[0] = 0;
+        // the data only arrives after more reads than the timeout allows
+        add_measurement_transactions(&mut transactions, no_data);
+        for _ in 0..10 {
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                no_data.to_vec(),
+            ));
+        }
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_SENSOR_RESULT],
+            FIELD_DATA.to_vec(),
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.set_min_polls(20);
+        assert!(bme.measure().is_ok());
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_with() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let expected_delay = bme.calculate_delay_period_us();
+        let mut delays = vec![];
+        let data = bme.measure_with(|us| delays.push(us)).unwrap();
+        assert!(data.temperature > 0.0);
+        assert_eq!(delays, [expected_delay]);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_poll_event() {
+        let mut transactions = setup_transactions();
+        let mut measuring = FIELD_DATA;
+        measuring[0] = 0b0_0_1_0_0000;
+        let mut gas_measuring = FIELD_DATA;
+        gas_measuring[0] = 0b0_1_1_0_0000;
+        // triggered and polled until the data is ready, then the next trigger is lost
+        add_measurement_transactions(&mut transactions, measuring);
+        for field_data in [gas_measuring, FIELD_DATA] {
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                field_data.to_vec(),
+            ));
+        }
+        add_measurement_transactions(&mut transactions, [0; 15]);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::Busy)));
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::Busy)));
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::GasNotReady)));
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::DataReady(_))));
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::Busy)));
+        assert!(matches!(bme.poll_event(), Ok(SensorEvent::Timeout)));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_with_tfine() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let (data, t_fine) = bme.measure_with_tfine().unwrap();
+        assert!((t_fine / 5120. - data.temperature).abs() < 0.001);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_gas_stable() {
+        let mut transactions = setup_transactions();
+        let mut other_range = FIELD_DATA;
+        other_range[14] = 0b11_1_1_0100;
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_measurement_transactions(&mut transactions, other_range);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let expected = MeasurementData::decode(
+            &FIELD_DATA,
+            &extract_calibration_data(CALIBRATION_DATA),
+            Variant::GasLow,
+        )
+        .unwrap();
+        let data = bme.measure_gas_stable(3).unwrap();
+        assert_eq!(data.gas_resistance, expected.gas_resistance);
+        assert_eq!(bme.measurement_count(), 1);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_set_gas_config() {
+        let mut transactions = setup_transactions();
+        let gas_config = GasConfig::new(core::time::Duration::from_millis(100), 250).unwrap();
+        let res_heat = gas_config.calc_res_heat(&extract_calibration_data(CALIBRATION_DATA), 20);
+        add_sleep_to_sleep_transactions(&mut transactions);
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_GAS_WAIT_0, gas_config.calc_gas_wait()],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_RES_HEAT_0, res_heat],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG, RAW_CONFIG[0]],
+        ));
+        // disabling clears run_gas
+        add_sleep_to_sleep_transactions(&mut transactions);
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG, 0],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.set_gas_config(Some(gas_config.clone())).unwrap();
+        assert_eq!(bme.current_sensor_config.gas_config, Some(gas_config));
+        bme.set_gas_config(None).unwrap();
+        assert!(!bme.sensor_config.run_gas());
+        assert_eq!(bme.sensor_config.0[1..], RAW_CONFIG[1..]);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_gas_meas_index() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_SENSOR_RESULT],
+            vec![0b1_0_0_0_0101],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(bme.gas_meas_index().unwrap(), 5);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_new_lazy() {
+        // no reset and chip id check, everything else happens on the first measurement
+        let mut transactions = setup_transactions().split_off(2);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new_lazy(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        );
+        bme.measure().unwrap();
+        assert_eq!(bme.sensor_config.0, RAW_CONFIG);
+        // calibration data is cached
+        bme.measure().unwrap();
+        bme.into_inner().done();
+    }
+    #[cfg(feature = "debug-internals")]
+    #[test]
+    fn test_measure_debug() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let debug = bme.measure_debug().unwrap();
+        assert_eq!(debug.field_data, FieldData::from(&RawData(FIELD_DATA)));
+        assert_eq!(debug.humidity_unclamped, debug.data.humidity);
+        assert!((debug.t_fine / 5120. - debug.data.temperature).abs() < 0.001);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_forced_mode_not_entered() {
+        let mut transactions = setup_transactions();
+        let mut no_data = FIELD_DATA;
+        no_data[0] = 0;
+        add_measurement_transactions(&mut transactions, no_data);
+        for _ in 0..8 {
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                no_data.to_vec(),
+            ));
+        }
+        // the forced mode write didn't take
+        add_sleep_to_sleep_transactions(&mut transactions);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(bme.measure(), Err(BmeError::ForcedModeNotEntered)));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_timeout_with_unknown_mode() {
+        let mut transactions = setup_transactions();
+        let mut no_data = FIELD_DATA;
+        no_data[0] = 0;
+        add_measurement_transactions(&mut transactions, no_data);
+        for _ in 0..8 {
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                no_data.to_vec(),
+            ));
+        }
+        // the mode is read back as 0xFF
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE],
+            vec![0xFF],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(
+            bme.measure(),
+            Err(BmeError::MeasuringTimeOut { attempts: 9 })
+        ));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_is_gas_running() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG],
+            vec![RAW_CONFIG[0]],
+        ));
+        // run_gas cleared, e.g. by a failed write
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG],
+            vec![0],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(bme.is_gas_running().unwrap());
+        assert!(!bme.is_gas_running().unwrap());
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_set_oversampling() {
+        let mut transactions = setup_transactions();
+        add_sleep_to_sleep_transactions(&mut transactions);
+        // humidity x4
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[1], 0b0_0_000_011],
+        ));
+        // temperature x1, pressure x1, sleep
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[3], 0b001_001_00],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.set_oversampling(Oversampling::By1, Oversampling::By1, Oversampling::By4)
+            .unwrap();
+        assert_eq!(
+            bme.current_sensor_config.humidity_oversampling,
+            Some(Oversampling::By4)
+        );
+        assert_eq!(bme.sensor_config.filter(), IIRFilter::Coeff1);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_current_mode() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_sleep_to_sleep_transactions(&mut transactions);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.measure().unwrap();
+        assert_eq!(bme.current_mode().unwrap(), SensorMode::Sleep);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_current_mode_unknown() {
+        let mut transactions = setup_transactions();
+        // glitching bus
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE],
+            vec![0xFF],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert!(matches!(
+            bme.current_mode(),
+            Err(BmeError::UnknownSensorMode(0b11))
+        ));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_delay_period() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        // 19 cycles * 1963us + 4 * 477us switching + 5 * 477us gas + 1ms wake up + 148ms heater duration
+        assert_eq!(bme.calculate_delay_period_us(), 190_590);
+        assert_eq!(bme.calculate_tph_duration_us(), 40_205);
+        bme.sensor_config
+            .set_temperature_oversampling(Oversampling::By1);
+        bme.sensor_config
+            .set_pressure_oversampling(Oversampling::By1);
+        bme.sensor_config
+            .set_humidity_oversampling(Oversampling::By1);
+        bme.sensor_config.set_run_gas(false);
+        assert_eq!(bme.calculate_delay_period_us(), 11_182);
+        bme.sensor_config
+            .set_temperature_oversampling(Oversampling::Skipped);
+        bme.sensor_config
+            .set_pressure_oversampling(Oversampling::Skipped);
+        bme.sensor_config
+            .set_humidity_oversampling(Oversampling::Skipped);
+        assert_eq!(bme.calculate_delay_period_us(), 5_293);
+        bme.into_inner().done();
+    }
+    #[cfg(feature = "trace")]
+    #[test]
+    fn test_trace() {
+        use std::sync::Mutex;
+        static OPS: Mutex<Vec<(u8, Vec<u8>)>> = Mutex::new(Vec::new());
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.set_trace(Some(|op| {
+            let op = match op {
+                RegisterOp::Read { addr, data } => (addr, data.to_vec()),
+                RegisterOp::Write { addr, value } => (addr, vec![value]),
+            };
+            OPS.lock().unwrap().push(op);
+        }));
+        bme.measure().unwrap();
+        assert_eq!(
+            *OPS.lock().unwrap(),
+            [
+                (ADDR_CONTROL_MODE, vec![CTRL_MEAS_SLEEP]),
+                (ADDR_CONTROL_MODE, vec![CTRL_MEAS_SLEEP | 1]),
+                (ADDR_SENSOR_RESULT, FIELD_DATA.to_vec()),
+            ]
+        );
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_then_trigger() {
+        let mut transactions = setup_transactions();
+        // first call triggers and waits
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        for _ in 0..2 {
+            // next measurement is triggered right away
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CONTROL_MODE],
+                vec![CTRL_MEAS_SLEEP],
+            ));
+            transactions.push(I2cTransaction::write(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CONTROL_MODE, CTRL_MEAS_SLEEP | 0b01],
+            ));
+            // and read by the next call
+            transactions.push(I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_SENSOR_RESULT],
+                FIELD_DATA.to_vec(),
+            ));
+        }
+        // the last measurement is triggered again
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE],
+            vec![CTRL_MEAS_SLEEP],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONTROL_MODE, CTRL_MEAS_SLEEP | 0b01],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            bme.measure_then_trigger().unwrap();
+        }
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_identify() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CHIP_ID],
+            vec![CHIP_ID],
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_VARIANT_ID],
+            vec![0],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        assert_eq!(bme.variant(), Variant::GasLow);
+        assert_eq!(bme.identify().unwrap(), (CHIP_ID, Variant::GasLow));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_calibration_from_raw_bytes() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let read = bme.get_calibration_data();
+        let calibration_data = CalibrationData::from_raw_bytes(CALIBRATION_DATA);
+        assert_eq!(calibration_data.par_t1, read.par_t1);
+        assert_eq!(calibration_data.par_h2, read.par_h2);
+        assert_eq!(calibration_data.range_sw_err, read.range_sw_err);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_read_field() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_SENSOR_RESULT],
+            FIELD_DATA.to_vec(),
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let field_data = bme.read_field().unwrap();
+        assert_eq!(
+            field_data,
+            FieldData {
+                new_data: true,
+                gas_measuring: false,
+                measuring: false,
+                gas_meas_index: 0,
+                pressure_adc: 0xCA000,
+                temperature_adc: 0x3EDB0,
+                humidity_adc: 0xCF39,
+                gas_adc: 0b10000001_11,
+                gas_valid: true,
+                heater_stable: true,
+                gas_range: 3,
+            }
+        );
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_warmup_discard() {
+        let mut transactions = setup_transactions();
+        // two discarded measurements
+        let mut discarded = FIELD_DATA;
+        discarded[8] = 0x00;
+        add_measurement_transactions(&mut transactions, discarded);
+        add_measurement_transactions(&mut transactions, discarded);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let config = Configuration::builder().warmup_discard(2).build();
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &config,
+            20,
+        )
+        .unwrap();
+        for _ in 0..2 {
+            let humidity = bme.measure().unwrap().humidity;
+            assert!((39.0..=41.0).contains(&humidity));
+        }
+        // discarded measurements aren't counted
+        assert_eq!(bme.measurement_count(), 2);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_warmup_discard_measure_n() {
+        let mut transactions = setup_transactions();
+        let mut discarded = FIELD_DATA;
+        discarded[8] = 0x00;
+        add_measurement_transactions(&mut transactions, discarded);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let config = Configuration::builder().warmup_discard(1).build();
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &config,
+            20,
+        )
+        .unwrap();
+        for data in bme.measure_n::<2>().unwrap() {
+            assert!((39.0..=41.0).contains(&data.humidity));
+        }
+        assert_eq!(bme.measurement_count(), 2);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_warmup_discard_measure_humidity() {
+        let mut transactions = setup_transactions();
+        // the humidity measurement runs with the heater off and doesn't use up the warm-up
+        let humidity_config = [0, 0b0_0_000_001, 0, 0b001_000_00, 0b000_001_00];
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, humidity_config);
+        add_measurement_transactions_with_ctrl_meas(
+            &mut transactions,
+            FIELD_DATA,
+            humidity_config[3],
+        );
+        add_sleep_to_sleep_transactions(&mut transactions);
+        add_raw_config_transactions(&mut transactions, RAW_CONFIG);
+        // the next measure still discards one measurement
+        let mut discarded = FIELD_DATA;
+        discarded[8] = 0x00;
+        add_measurement_transactions(&mut transactions, discarded);
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let config = Configuration::builder().warmup_discard(1).build();
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &config,
+            20,
+        )
+        .unwrap();
+        let humidity = bme.measure_humidity().unwrap();
+        assert!((39.0..=41.0).contains(&humidity));
+        let humidity = bme.measure().unwrap().humidity;
+        assert!((39.0..=41.0).contains(&humidity));
+        assert_eq!(bme.measurement_count(), 2);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_release() {
+        let mut transactions = setup_transactions();
+        add_sleep_to_sleep_transactions(&mut transactions);
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.release().unwrap().done();
+    }
+    #[test]
+    fn test_retry_policy() {
+        let mut transactions = setup_transactions();
+        transactions.push(
+            I2cTransaction::write_read(
+                DeviceAddress::Primary.into(),
+                vec![ADDR_CONTROL_MODE],
+                vec![0],
+            )
+            .with_error(embedded_hal::i2c::ErrorKind::NoAcknowledge(
+                embedded_hal::i2c::NoAcknowledgeSource::Address,
+            )),
+        );
+        add_sleep_to_sleep_transactions(&mut transactions);
+        let i2c_interface = I2cMock::new(&transactions);
+        let bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap()
+        .with_retry_policy(RetryPolicy {
+            retries: 2,
+            delay_us: 50,
+        });
+        bme.release().unwrap().done();
+    }
+    #[test]
+    fn test_write_verification() {
+        let mut transactions = setup_transactions();
+        add_sleep_to_sleep_transactions(&mut transactions);
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_CONFIG],
+            RAW_CONFIG.to_vec(),
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[0], RAW_CONFIG[0]],
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[0]],
+            vec![RAW_CONFIG[0]],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[1], RAW_CONFIG[1]],
+        ));
+        // corrupted write
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![ADDRS_CONFIG[1]],
+            vec![0],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.set_write_verification(true);
+        // writes the same registers as the default config
+        let config = Configuration::builder().delay_margin_percent(10).build();
+        let result = bme.set_configuration(&config);
+        assert!(matches!(
+            result,
+            Err(BmeError::WriteVerificationFailed {
+                addr: 0x72,
+                wrote: 1,
+                read: 0
+            })
+        ));
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_no_gas_wait() {
+        let mut transactions = setup_transactions();
+        let mut field_data = FIELD_DATA;
+        // measuring and gas_measuring still set
+        field_data[0] = 0b1_1_1_0_0000;
+        add_measurement_transactions(&mut transactions, field_data);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let data = bme.measure_no_gas_wait().unwrap();
+        assert!(data.gas_resistance.is_none());
+        bme.into_inner().done();
+    }
+    #[cfg(feature = "raw-register-access")]
+    #[test]
+    fn test_raw_register_access() {
+        let mut transactions = setup_transactions();
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![0x50, 0x12],
+        ));
+        transactions.push(I2cTransaction::write_read(
+            DeviceAddress::Primary.into(),
+            vec![0x50],
+            vec![0x12],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        bme.write_register(0x50, 0x12).unwrap();
+        assert_eq!(bme.read_register(0x50).unwrap(), 0x12);
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_set_same_configuration() {
+        let transactions = setup_transactions();
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        // no transactions expected
+        bme.set_configuration(&Configuration::default()).unwrap();
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_set_same_configuration_refreshes_heater() {
+        let mut transactions = setup_transactions();
+        let gas_config = GasConfig::default();
+        let res_heat = gas_config.calc_res_heat(&extract_calibration_data(CALIBRATION_DATA), 20);
+        // only the heater registers are rewritten
+        add_sleep_to_sleep_transactions(&mut transactions);
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_GAS_WAIT_0, gas_config.calc_gas_wait()],
+        ));
+        transactions.push(I2cTransaction::write(
+            DeviceAddress::Primary.into(),
+            vec![ADDR_RES_HEAT_0, res_heat],
+        ));
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        // as if the heater target had been calculated for a different ambient temperature
+        bme.i2c.res_heat_0 = Some(res_heat + 1);
+        bme.set_configuration(&Configuration::default()).unwrap();
+        assert_eq!(bme.i2c.res_heat_0, Some(res_heat));
+        // the heater target is up to date now
+        bme.set_configuration(&Configuration::default()).unwrap();
+        bme.into_inner().done();
+    }
+    #[test]
+    fn test_measure_with_buffer() {
+        let mut transactions = setup_transactions();
+        add_measurement_transactions(&mut transactions, FIELD_DATA);
+        let i2c_interface = I2cMock::new(&transactions);
+        let mut bme = Bme680::new(
+            i2c_interface,
+            DeviceAddress::Primary,
+            NoopDelay::new(),
+            &Configuration::default(),
+            20,
+        )
+        .unwrap();
+        let mut buffer = [0; 15];
+        bme.measure_with_buffer(&mut buffer).unwrap();
+        assert_eq!(buffer, FIELD_DATA);
+        bme.into_inner().done();
+    }
 }
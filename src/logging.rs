@@ -0,0 +1,19 @@
+// Logging macros that forward to the log crate if the `log` feature is enabled.
+// Without the feature they expand to nothing but still type check their arguments.
+#[cfg(feature = "log")]
+pub(crate) use log::{debug, warn};
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+#[cfg(not(feature = "log"))]
+pub(crate) use {log_debug as debug, log_warn as warn};
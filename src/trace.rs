@@ -0,0 +1,12 @@
+/// A register access reported to the callback set with [`Bme680::set_trace`](crate::Bme680::set_trace).
+/// Only successful transfers are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterOp<'a> {
+    /// Registers read starting at `addr`
+    Read { addr: u8, data: &'a [u8] },
+    /// Value written to the register at `addr`
+    Write { addr: u8, value: u8 },
+}
+
+/// Callback receiving every register access of the driver.
+pub type Tracer = fn(RegisterOp<'_>);
@@ -0,0 +1,17 @@
+use bosch_bme680::Bme680;
+use embedded_hal_mock::eh1::{delay::NoopDelay, i2c::Mock as I2cMock};
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+// The driver only contains plain data besides the i2c interface and the delay,
+// so it has to be Send/Sync whenever those are.
+#[test]
+fn test_driver_is_send() {
+    assert_send::<Bme680<I2cMock, NoopDelay>>();
+}
+
+#[test]
+fn test_driver_is_sync() {
+    assert_sync::<Bme680<I2cMock, NoopDelay>>();
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "std")]
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use bosch_bme680::{Bme680, BmeError, Configuration, DeviceAddress};
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+use embedded_hal_mock::eh1::delay::NoopDelay;
+
+// bus error implementing std::error::Error, which ErrorKind doesn't
+#[derive(Debug)]
+struct BusError;
+
+impl Display for BusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("bus error")
+    }
+}
+impl Error for BusError {}
+impl i2c::Error for BusError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Bus
+    }
+}
+
+// fails every transfer
+struct BrokenBus;
+
+impl ErrorType for BrokenBus {
+    type Error = BusError;
+}
+impl I2c<SevenBitAddress> for BrokenBus {
+    fn transaction(
+        &mut self,
+        _address: SevenBitAddress,
+        _operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        Err(BusError)
+    }
+}
+
+fn setup() -> Result<Bme680<BrokenBus, NoopDelay>, Box<dyn Error>> {
+    let bme = Bme680::new(
+        BrokenBus,
+        DeviceAddress::Primary,
+        NoopDelay::new(),
+        &Configuration::default(),
+        20,
+    )?;
+    Ok(bme)
+}
+
+#[test]
+fn test_box_dyn_error() {
+    let error = setup().err().unwrap();
+    assert!(error.is::<BmeError<BrokenBus>>());
+    assert!(error.source().unwrap().is::<BusError>());
+    assert_eq!(
+        error.to_string(),
+        "i2c write of register 0xe0 failed: BusError"
+    );
+}